@@ -0,0 +1,338 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    prelude::*,
+    render::view::VisibilitySystems,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    transform::TransformSystem,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    layout::HoveredTile,
+    theme::{Theme, ThemeColor},
+    AppState, GameData, OnGameScreen, Tile, WinPossibilities,
+};
+
+pub struct AnimationPlugin;
+
+/// Time one stroke of a symbol takes at normal speed
+const STROKE_SECONDS: f32 = 0.15;
+/// Time the winning tiles flash before the result shows, at normal speed
+const FLASH_SECONDS: f32 = 0.9;
+/// How often the winning tiles light up while flashing
+const FLASHES: f32 = 3.0;
+/// Time the result overlay takes to fade in at normal speed
+const FADE_SECONDS: f32 = 0.3;
+/// Time the hovered tile takes to shrink and grow back at normal speed
+const HOVER_PULSE_SECONDS: f32 = 1.2;
+
+/// How fast the board animates. Off shows every change at once, for players
+/// who are bothered by motion.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationSpeed {
+    Off,
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    pub fn next(&self) -> Self {
+        match self {
+            AnimationSpeed::Off => AnimationSpeed::Slow,
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Off,
+        }
+    }
+
+    /// How many times faster than normal animations play, `None` when they
+    /// are off
+    fn rate(self) -> Option<f32> {
+        match self {
+            AnimationSpeed::Off => None,
+            AnimationSpeed::Slow => Some(0.5),
+            AnimationSpeed::Normal => Some(1.0),
+            AnimationSpeed::Fast => Some(2.0),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("Animations: {}", self)
+    }
+}
+
+impl std::fmt::Display for AnimationSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimationSpeed::Off => write!(f, "Off"),
+            AnimationSpeed::Slow => write!(f, "Slow"),
+            AnimationSpeed::Normal => write!(f, "Normal"),
+            AnimationSpeed::Fast => write!(f, "Fast"),
+        }
+    }
+}
+
+/// Stroke of a symbol that is still being drawn. The symbol is spawned in its
+/// final shape, this component winds it back and lets it grow.
+#[derive(Component)]
+pub struct Drawing {
+    stroke: Stroke,
+    /// Seconds at normal speed before the stroke starts
+    delay: f32,
+    elapsed: f32,
+}
+
+enum Stroke {
+    /// Bar of an X centred on `center`, drawn from one end to the other
+    Line { center: Vec2, length: f32 },
+    /// An O, traced counterclockwise from the top
+    Arc { radius: f32 },
+}
+
+impl Drawing {
+    /// Bar of an X. Strokes with a higher `order` start once the ones before
+    /// them are done.
+    pub fn line(center: Vec2, length: f32, order: u32) -> Self {
+        Self {
+            stroke: Stroke::Line { center, length },
+            delay: order as f32 * STROKE_SECONDS,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn arc(radius: f32) -> Self {
+        Self {
+            stroke: Stroke::Arc { radius },
+            delay: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// Overlay flashing on a tile of the winning line
+#[derive(Component)]
+struct WinFlash {
+    elapsed: f32,
+}
+
+/// Result overlay, hidden until the winning tiles are done flashing and then
+/// faded in
+#[derive(Component)]
+pub struct FadeIn {
+    delay: f32,
+    elapsed: f32,
+}
+
+impl FadeIn {
+    pub fn result(win: &WinPossibilities) -> Self {
+        Self {
+            // Nothing flashes after a draw
+            delay: if *win == WinPossibilities::Tie {
+                0.0
+            } else {
+                FLASH_SECONDS
+            },
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnimationSpeed>()
+            .add_systems(
+                Update,
+                (
+                    flash_winning_tiles.run_if(resource_added::<WinPossibilities>),
+                    animate_win_flashes,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_exists::<GameData>),
+            )
+            .add_systems(
+                Update,
+                pulse_hovered_tile
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::InEditor))),
+            )
+            // Symbols and overlays are spawned in their final state during
+            // `Update`, this winds them back before they are first drawn
+            .add_systems(
+                PostUpdate,
+                (draw_symbols, fade_in_results)
+                    .before(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::VisibilityPropagate),
+            );
+    }
+}
+
+/// Share of an animation done after `elapsed` seconds at normal speed, eased
+/// in and out
+fn progress(elapsed: f32, delay: f32, duration: f32) -> f32 {
+    let t = ((elapsed - delay) / duration).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn draw_symbols(
+    time: Res<Time>,
+    speed: Res<AnimationSpeed>,
+    mut drawings: Query<(Entity, &mut Drawing, &mut Transform, &Mesh2dHandle)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    for (entity, mut drawing, mut transform, mesh) in &mut drawings {
+        let done = match speed.rate() {
+            Some(rate) => {
+                drawing.elapsed += time.delta_seconds() * rate;
+                progress(drawing.elapsed, drawing.delay, STROKE_SECONDS)
+            }
+            None => 1.0,
+        };
+        match drawing.stroke {
+            Stroke::Line { center, length } => {
+                let direction = (transform.rotation * Vec3::X).truncate();
+                let drawn = length * done;
+                transform.scale.x = drawn;
+                transform.translation =
+                    (center + direction * (drawn - length) / 2.0).extend(transform.translation.z);
+            }
+            Stroke::Arc { radius } => {
+                // The sector opens to both sides of its axis, turning it by
+                // the same angle keeps the start of the arc at the top
+                let half_angle = PI * done;
+                meshes.insert(&mesh.0, CircularSector::new(radius, half_angle).into());
+                transform.rotation = Quat::from_rotation_z(half_angle);
+            }
+        }
+        if done >= 1.0 {
+            commands.entity(entity).remove::<Drawing>();
+        }
+    }
+}
+
+fn flash_winning_tiles(
+    game_data: Res<GameData>,
+    tiles: Query<&Tile>,
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let winning = game_data.winning_tiles();
+    for tile in tiles.iter().filter(|tile| winning.contains(&tile.index)) {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(Rectangle::default()).into(),
+                transform: Transform::default()
+                    .with_scale(Vec3::splat(tile.size))
+                    .with_translation(tile.pos.extend(0.18)),
+                material: materials.add(theme.palette().highlight.with_alpha(0.0)),
+                ..default()
+            },
+            WinFlash { elapsed: 0.0 },
+            ThemeColor::Highlight,
+            OnGameScreen,
+        ));
+    }
+}
+
+/// Lets the winning tiles light up a few times, then keeps them lit until
+/// the game is taken back or replayed
+fn animate_win_flashes(
+    time: Res<Time>,
+    speed: Res<AnimationSpeed>,
+    win: Option<Res<WinPossibilities>>,
+    mut flashes: Query<(Entity, &mut WinFlash, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, mut flash, material) in &mut flashes {
+        if win.is_none() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = match speed.rate() {
+            Some(rate) if flash.elapsed < FLASH_SECONDS => {
+                flash.elapsed += time.delta_seconds() * rate;
+                let phase = flash.elapsed / FLASH_SECONDS * FLASHES * TAU;
+                0.3 * (1.0 - phase.cos())
+            }
+            _ => 0.5,
+        };
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_alpha(alpha);
+        }
+    }
+}
+
+/// Keeps the result overlay hidden while the winning tiles flash and fades
+/// it in afterwards
+fn fade_in_results(
+    time: Res<Time>,
+    speed: Res<AnimationSpeed>,
+    mut overlays: Query<(Entity, &mut FadeIn, &mut Visibility)>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+    mut buttons: Query<(&mut BackgroundColor, &mut BorderColor), With<Button>>,
+    mut commands: Commands,
+) {
+    for (entity, mut fade, mut visibility) in &mut overlays {
+        let done = match speed.rate() {
+            Some(rate) => {
+                fade.elapsed += time.delta_seconds() * rate;
+                progress(fade.elapsed, fade.delay, FADE_SECONDS)
+            }
+            None => 1.0,
+        };
+        // Hidden buttons can't be pressed, so the overlay doesn't take clicks
+        // before it shows
+        *visibility = if fade.elapsed < fade.delay && done < 1.0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut text) = texts.get_mut(child) {
+                for section in &mut text.sections {
+                    section.style.color.set_alpha(done);
+                }
+            }
+            if let Ok((mut background, mut border)) = buttons.get_mut(child) {
+                background.0.set_alpha(done);
+                border.0.set_alpha(done);
+            }
+        }
+        if done >= 1.0 {
+            commands.entity(entity).remove::<FadeIn>();
+        }
+    }
+}
+
+/// Lets the tile under the mouse shrink and grow back while it is hovered
+fn pulse_hovered_tile(
+    time: Res<Time>,
+    speed: Res<AnimationSpeed>,
+    hovered: Res<HoveredTile>,
+    mut tiles: Query<(&Tile, &mut Transform)>,
+    mut since: Local<f32>,
+) {
+    if hovered.is_changed() {
+        *since = time.elapsed_seconds();
+    }
+    let elapsed = time.elapsed_seconds() - *since;
+    for (tile, mut transform) in &mut tiles {
+        let size = match speed.rate() {
+            Some(rate) if hovered.0 == Some(tile.index) => {
+                let phase = elapsed * rate / HOVER_PULSE_SECONDS * TAU;
+                tile.size * (1.0 - 0.03 * (1.0 - phase.cos()))
+            }
+            _ => tile.size,
+        };
+        if transform.scale.x != size {
+            transform.scale = Vec3::splat(size);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::time::Duration;
+
+use bevy::{asset::LoadState, audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{GameData, Mode, PlayerChoice, WinPossibilities};
+
+pub struct SoundPlugin;
+
+/// Background music, looped while it is switched on. None of the audio files
+/// ship with the game, they are optional: missing effects are replaced by a
+/// plain tone and without this file there is no music.
+const MUSIC_FILE: &str = "audio/music.ogg";
+
+/// Sound effects of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Place,
+    Invalid,
+    Win,
+    Loss,
+    Draw,
+    Hover,
+}
+
+impl Sound {
+    const ALL: [Sound; 6] = [
+        Sound::Place,
+        Sound::Invalid,
+        Sound::Win,
+        Sound::Loss,
+        Sound::Draw,
+        Sound::Hover,
+    ];
+
+    /// File of the sound, relative to the assets folder
+    fn path(self) -> &'static str {
+        match self {
+            Sound::Place => "audio/place.ogg",
+            Sound::Invalid => "audio/invalid.ogg",
+            Sound::Win => "audio/win.ogg",
+            Sound::Loss => "audio/loss.ogg",
+            Sound::Draw => "audio/draw.ogg",
+            Sound::Hover => "audio/hover.ogg",
+        }
+    }
+
+    /// Frequency and length of the plain tone played when the file is missing
+    fn tone(self) -> (f32, f32) {
+        match self {
+            Sound::Place => (660.0, 0.08),
+            Sound::Invalid => (180.0, 0.15),
+            Sound::Win => (880.0, 0.4),
+            Sound::Loss => (220.0, 0.5),
+            Sound::Draw => (440.0, 0.3),
+            Sound::Hover => (1200.0, 0.02),
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PlaySound(pub Sound);
+
+/// One of the volume sliders of the settings screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Master,
+    Effects,
+    Music,
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Channel::Master => write!(f, "Master"),
+            Channel::Effects => write!(f, "Effects"),
+            Channel::Music => write!(f, "Music"),
+        }
+    }
+}
+
+/// Volumes between 0 and 1. Effects and music are scaled by the master
+/// volume.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeSettings {
+    master: f32,
+    effects: f32,
+    music: f32,
+    pub music_on: bool,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            master: 0.8,
+            effects: 1.0,
+            music: 0.5,
+            music_on: true,
+        }
+    }
+}
+
+impl VolumeSettings {
+    pub fn get(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Effects => self.effects,
+            Channel::Music => self.music,
+        }
+    }
+
+    /// Brings volumes edited by hand back between 0 and 1
+    pub fn clamp(&mut self) {
+        for channel in [Channel::Master, Channel::Effects, Channel::Music] {
+            self.set(channel, self.get(channel));
+        }
+    }
+
+    /// Sets a volume, kept between 0 and 1 and rounded to whole percents
+    pub fn set(&mut self, channel: Channel, volume: f32) {
+        let volume = (volume.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        match channel {
+            Channel::Master => self.master = volume,
+            Channel::Effects => self.effects = volume,
+            Channel::Music => self.music = volume,
+        }
+    }
+
+    fn effects_volume(&self) -> f32 {
+        self.master * self.effects
+    }
+
+    fn music_volume(&self) -> f32 {
+        if self.music_on {
+            self.master * self.music
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Handles of every sound, loaded once at startup
+#[derive(Resource)]
+struct SoundAssets {
+    /// Indexed by [`Sound`]
+    files: Vec<Handle<AudioSource>>,
+    tones: Vec<Handle<Pitch>>,
+    music: Handle<AudioSource>,
+}
+
+#[derive(Component)]
+struct Music;
+
+/// Whether the music file has been loaded, the settings only offer the music
+/// once it is
+#[derive(Resource, Default)]
+pub struct MusicAvailable(pub bool);
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VolumeSettings>()
+            .init_resource::<MusicAvailable>()
+            .add_systems(Startup, load_sounds)
+            .add_systems(
+                Update,
+                (
+                    hover_sound,
+                    result_sound.run_if(resource_added::<WinPossibilities>),
+                    play_sounds,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    detect_music.run_if(|music: Res<MusicAvailable>| !music.0),
+                    start_music.run_if(not(any_with_component::<Music>)),
+                    update_music.run_if(resource_changed::<VolumeSettings>),
+                ),
+            );
+    }
+}
+
+fn load_sounds(
+    asset_server: Res<AssetServer>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(SoundAssets {
+        files: Sound::ALL
+            .iter()
+            .map(|sound| asset_server.load(sound.path()))
+            .collect(),
+        tones: Sound::ALL
+            .iter()
+            .map(|sound| {
+                let (frequency, seconds) = sound.tone();
+                pitches.add(Pitch::new(frequency, Duration::from_secs_f32(seconds)))
+            })
+            .collect(),
+        music: asset_server.load(MUSIC_FILE),
+    });
+}
+
+fn hover_sound(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Hovered)
+    {
+        sounds.send(PlaySound(Sound::Hover));
+    }
+}
+
+/// Cheers the human's wins and mourns their losses
+fn result_sound(
+    win: Res<WinPossibilities>,
+    game_data: Option<Res<GameData>>,
+    mut sounds: EventWriter<PlaySound>,
+) {
+    let lost = game_data.is_some_and(|data| {
+        data.mode == Mode::Computer
+            && matches!(
+                (&*win, data.player),
+                (WinPossibilities::XWon, PlayerChoice::O)
+                    | (WinPossibilities::OWon, PlayerChoice::X)
+            )
+    });
+    let sound = match *win {
+        WinPossibilities::None => return,
+        WinPossibilities::Tie => Sound::Draw,
+        _ if lost => Sound::Loss,
+        _ => Sound::Win,
+    };
+    sounds.send(PlaySound(sound));
+}
+
+/// Plays the requested sounds, falling back to a plain tone for every file
+/// that couldn't be loaded
+fn play_sounds(
+    mut events: EventReader<PlaySound>,
+    sounds: Option<Res<SoundAssets>>,
+    asset_server: Res<AssetServer>,
+    volume: Res<VolumeSettings>,
+    mut commands: Commands,
+) {
+    let Some(sounds) = sounds else {
+        return;
+    };
+    let settings = PlaybackSettings::DESPAWN.with_volume(Volume::new(volume.effects_volume()));
+    for PlaySound(sound) in events.read() {
+        if volume.effects_volume() <= 0.0 {
+            continue;
+        }
+        let file = &sounds.files[*sound as usize];
+        if matches!(asset_server.load_state(file), LoadState::Failed(_)) {
+            commands.spawn(PitchBundle {
+                source: sounds.tones[*sound as usize].clone(),
+                settings,
+            });
+        } else {
+            commands.spawn(AudioBundle {
+                source: file.clone(),
+                settings,
+            });
+        }
+    }
+}
+
+fn detect_music(
+    sounds: Option<Res<SoundAssets>>,
+    asset_server: Res<AssetServer>,
+    mut available: ResMut<MusicAvailable>,
+) {
+    if let Some(sounds) = sounds {
+        available.0 = matches!(asset_server.load_state(&sounds.music), LoadState::Loaded);
+    }
+}
+
+/// Starts the music once it is switched on and its file is loaded. There is
+/// no music without the file.
+fn start_music(
+    sounds: Option<Res<SoundAssets>>,
+    asset_server: Res<AssetServer>,
+    volume: Res<VolumeSettings>,
+    mut commands: Commands,
+) {
+    let Some(sounds) = sounds.filter(|_| volume.music_on) else {
+        return;
+    };
+    if !matches!(asset_server.load_state(&sounds.music), LoadState::Loaded) {
+        return;
+    }
+    commands.spawn((
+        AudioBundle {
+            source: sounds.music.clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(volume.music_volume())),
+        },
+        Music,
+    ));
+}
+
+fn update_music(volume: Res<VolumeSettings>, music: Query<&AudioSink, With<Music>>) {
+    for sink in &music {
+        sink.set_volume(volume.music_volume());
+        if volume.music_on {
+            sink.play();
+        } else {
+            sink.pause();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_are_clamped_and_scaled_by_master() {
+        let mut volume = VolumeSettings::default();
+        volume.set(Channel::Master, 0.5);
+        volume.set(Channel::Effects, 1.7);
+        volume.set(Channel::Music, 0.333);
+        assert_eq!(volume.get(Channel::Effects), 1.0);
+        assert_eq!(volume.effects_volume(), 0.5);
+        assert!((volume.music_volume() - 0.165).abs() < 1e-6);
+
+        volume.music_on = false;
+        assert_eq!(volume.music_volume(), 0.0);
+        volume.set(Channel::Master, -1.0);
+        assert_eq!(volume.get(Channel::Master), 0.0);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::AnimationSpeed, audio::VolumeSettings, menu::GameSettings, theme::Theme};
+
+pub struct ConfigPlugin;
+
+/// Version of the settings file written by this build. There is no upgrade
+/// path from other versions, their files are ignored and the settings reset
+/// to their defaults.
+const SETTINGS_VERSION: u32 = 1;
+
+/// Wait after the last change before the settings are written, so dragging a
+/// slider doesn't write the file every frame
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Everything that is kept between launches. Fields missing in the file take
+/// their defaults, so new ones don't need a new version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    version: u32,
+    game: GameSettings,
+    theme: Theme,
+    volume: VolumeSettings,
+    animations: AnimationSpeed,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            game: GameSettings::default(),
+            theme: Theme::default(),
+            volume: VolumeSettings::default(),
+            animations: AnimationSpeed::default(),
+        }
+    }
+}
+
+impl SettingsFile {
+    /// Reads a settings file, `None` if it is corrupt or of any other version
+    /// than [`SETTINGS_VERSION`]
+    fn parse(text: &str) -> Option<Self> {
+        let mut file = ron::from_str::<Self>(text)
+            .map_err(|error| warn!("Ignoring corrupt settings file: {}", error))
+            .ok()?;
+        if file.version != SETTINGS_VERSION {
+            warn!(
+                "Ignoring settings file of version {}, resetting to the defaults",
+                file.version
+            );
+            return None;
+        }
+        file.game.clamp();
+        file.volume.clamp();
+        Some(file)
+    }
+}
+
+/// Countdown to writing the settings, restarted by every change
+#[derive(Resource, Default)]
+struct PendingSave(Option<Timer>);
+
+impl PendingSave {
+    fn restart(&mut self) {
+        self.0 = Some(Timer::new(SAVE_DELAY, TimerMode::Once));
+    }
+
+    /// Counts down, true once the settings are due to be written. Changes are
+    /// written right away when the app is closing.
+    fn due(&mut self, delta: Duration, closing: bool) -> bool {
+        let Some(timer) = &mut self.0 else {
+            return false;
+        };
+        if timer.tick(delta).finished() || closing {
+            self.0 = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// Settings file in the platform's config directory, `$XDG_CONFIG_HOME` or
+/// `~/.config` on Linux. There is none on the web.
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("tictactoe").join("settings.ron"))
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        // Loaded right away, so the menu and the theme start out with it
+        let file = settings_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| SettingsFile::parse(&text))
+            .unwrap_or_default();
+        app.insert_resource(file.game)
+            .insert_resource(file.theme)
+            .insert_resource(file.volume)
+            .insert_resource(file.animations)
+            .init_resource::<PendingSave>()
+            .add_systems(
+                Last,
+                (
+                    (|mut pending: ResMut<PendingSave>| pending.restart()).run_if(
+                        settings_changed::<GameSettings>
+                            .or_else(settings_changed::<Theme>)
+                            .or_else(settings_changed::<VolumeSettings>)
+                            .or_else(settings_changed::<AnimationSpeed>),
+                    ),
+                    save_settings,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Changed since startup, inserting the loaded settings doesn't count
+fn settings_changed<T: Resource>(settings: Res<T>) -> bool {
+    settings.is_changed() && !settings.is_added()
+}
+
+fn save_settings(
+    time: Res<Time>,
+    mut pending: ResMut<PendingSave>,
+    mut exit: EventReader<AppExit>,
+    game: Res<GameSettings>,
+    theme: Res<Theme>,
+    volume: Res<VolumeSettings>,
+    animations: Res<AnimationSpeed>,
+) {
+    let closing = exit.read().count() > 0;
+    if !pending.due(time.delta(), closing) {
+        return;
+    }
+    let Some(path) = settings_path() else {
+        return;
+    };
+    let file = SettingsFile {
+        version: SETTINGS_VERSION,
+        game: game.clone(),
+        theme: *theme,
+        volume: *volume,
+        animations: *animations,
+    };
+    if let Err(error) = write_ron(&path, &file) {
+        warn!(
+            "Couldn't save the settings to {}: {}",
+            path.display(),
+            error
+        );
+    }
+}
+
+/// Writes `value` to `path` as RON, creating the directories on the way
+pub fn write_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+    }
+    fs::write(path, text).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audio::Channel, theme::ThemeKind, PlayerChoice};
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let mut file = SettingsFile::default();
+        file.game.side = PlayerChoice::O;
+        file.game.rows = 5;
+        file.theme.kind = ThemeKind::HighContrast;
+        file.volume.set(Channel::Music, 0.2);
+        file.animations = AnimationSpeed::Off;
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(SettingsFile::parse(&text), Some(file));
+    }
+
+    #[test]
+    fn bad_files_fall_back_to_defaults() {
+        assert_eq!(SettingsFile::parse("not ron at all"), None);
+        assert_eq!(SettingsFile::parse("(version: 99)"), None);
+
+        // Missing fields are filled in and hand edited values clamped
+        let file = SettingsFile::parse("(version: 1, game: (rows: 40, win_length: 9))").unwrap();
+        assert_eq!(file.game.rows, 7);
+        assert_eq!(file.game.cols, 3);
+        assert_eq!(file.game.win_length, 3);
+        assert_eq!(file.volume, VolumeSettings::default());
+    }
+
+    #[test]
+    fn saves_wait_for_the_changes_to_settle() {
+        let mut pending = PendingSave::default();
+        assert!(!pending.due(SAVE_DELAY, false));
+
+        pending.restart();
+        assert!(!pending.due(SAVE_DELAY / 2, false));
+        // Another change, e.g. the next frame of a slider drag
+        pending.restart();
+        assert!(!pending.due(SAVE_DELAY / 2, false));
+        assert!(pending.due(SAVE_DELAY / 2, false));
+        assert!(!pending.due(SAVE_DELAY, false));
+
+        pending.restart();
+        assert!(pending.due(Duration::ZERO, true));
+    }
+}
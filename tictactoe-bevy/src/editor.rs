@@ -0,0 +1,493 @@
+use std::{fs, path::PathBuf, str::FromStr};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use tictactoe_logic::{
+    grid::{FieldStates, Grid},
+    minimax::MiniMax,
+    rules::{Classic, Rules, Status},
+};
+
+use crate::{
+    despawn_screen,
+    menu::GameSettings,
+    position::Position,
+    spawn_symbol, spawn_tiles,
+    theme::{spawn_button, Theme},
+    AppState, ButtonInteractions, GameData, PlayerChoice, Pressed, Tile, Variant,
+};
+
+/// Positions are exported to and imported from this file in the platform's
+/// data directory, next to the saved games, in the text format of `Grid`'s
+/// `Display` and `FromStr`
+fn position_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("tictactoe").join("position.txt"))
+}
+
+pub struct EditorPlugin;
+
+#[derive(Component, Clone)]
+struct OnEditorScreen;
+
+/// Marks the symbol standing on the tile with the given index
+#[derive(Component, Clone)]
+struct EditorSymbol(u32);
+
+#[derive(Component)]
+struct SideToMoveText;
+
+#[derive(Component)]
+struct EditorStatusText;
+
+#[derive(Component)]
+enum EditorButtonAction {
+    ToggleSideToMove,
+    Analyse,
+    Import,
+    Export,
+    PlayX,
+    PlayO,
+    Back,
+}
+
+/// Position set up in the editor, kept between visits. Positions are checked
+/// against the classic rules and games started from them are classic games.
+#[derive(Resource)]
+pub struct EditorData {
+    grid: Grid,
+    to_move: PlayerChoice,
+}
+
+impl Default for EditorData {
+    fn default() -> Self {
+        Self {
+            grid: Grid::new(3, 3),
+            to_move: PlayerChoice::X,
+        }
+    }
+}
+
+impl EditorData {
+    fn validate(&self) -> Result<Classic, String> {
+        Classic::setup(self.grid.clone(), self.to_move.to_field_states())
+    }
+
+    fn status(&self) -> String {
+        match self.validate() {
+            Ok(_) => "Legal position".to_string(),
+            Err(error) => error,
+        }
+    }
+
+    fn count(&self, state: FieldStates) -> usize {
+        self.grid
+            .clone()
+            .into_iter()
+            .filter(|field| *field == state)
+            .count()
+    }
+
+    /// Side whose turn it is by the number of symbols on the board, X when
+    /// both have placed as many
+    fn expected_to_move(&self) -> PlayerChoice {
+        if self.count(FieldStates::Player1) > self.count(FieldStates::Player2) {
+            PlayerChoice::O
+        } else {
+            PlayerChoice::X
+        }
+    }
+
+    fn symbol_at(&self, grid_index: u32) -> Option<PlayerChoice> {
+        self.grid
+            .get_elem(grid_index as usize)
+            .and_then(|state| PlayerChoice::from_field_states(*state))
+    }
+}
+
+/// Analysis running on the [`AsyncComputeTaskPool`], dropped and with it
+/// cancelled as soon as the position is edited
+#[derive(Resource)]
+struct EditorAnalysis(Task<String>);
+
+type EditorTexts<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Text, Has<SideToMoveText>),
+    Or<(With<SideToMoveText>, With<EditorStatusText>)>,
+>;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorData>()
+            .add_systems(OnEnter(AppState::InEditor), setup_editor)
+            .add_systems(Update, editor_action.run_if(in_state(AppState::InEditor)))
+            .add_systems(
+                Update,
+                poll_analysis
+                    .run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<EditorAnalysis>),
+            )
+            .add_systems(OnExit(AppState::InEditor), despawn_screen::<OnEditorScreen>)
+            .add_systems(OnExit(AppState::InEditor), |mut commands: Commands| {
+                commands.remove_resource::<EditorAnalysis>()
+            });
+    }
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<GameSettings>,
+    mut editor: ResMut<EditorData>,
+    theme: Res<Theme>,
+) {
+    let palette = theme.palette();
+    // Start over when the board size was changed in the menu
+    let grid = settings.grid();
+    if editor.grid.size() != grid.size() || editor.grid.win_length() != grid.win_length() {
+        *editor = EditorData {
+            grid,
+            to_move: PlayerChoice::X,
+        };
+    }
+
+    let mut observer = Observer::new(editor_tile_pressed);
+    let tiles = spawn_tiles(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &theme,
+        &mut observer,
+        OnEditorScreen,
+        &editor.grid,
+    );
+    commands.spawn((observer, OnEditorScreen));
+    for tile in &tiles {
+        if let Some(symbol) = editor.symbol_at(tile.index) {
+            spawn_symbol(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &theme,
+                tile,
+                symbol,
+                (OnEditorScreen, EditorSymbol(tile.index)),
+            );
+        }
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnEditorScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Set up a classic game",
+                        palette.text_style(),
+                    ));
+                    spawn_button(parent, palette, EditorButtonAction::ToggleSideToMove)
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    format!("To move: {:?}", editor.to_move),
+                                    palette.text_style(),
+                                ),
+                                SideToMoveText,
+                            ));
+                        });
+                    parent.spawn((
+                        TextBundle::from_section(editor.status(), palette.text_style()),
+                        EditorStatusText,
+                    ));
+                });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        column_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (label, action) in [
+                        ("Analyse", EditorButtonAction::Analyse),
+                        ("Import", EditorButtonAction::Import),
+                        ("Export", EditorButtonAction::Export),
+                        ("Play X", EditorButtonAction::PlayX),
+                        ("Play O", EditorButtonAction::PlayO),
+                        ("Back", EditorButtonAction::Back),
+                    ] {
+                        spawn_button(parent, palette, action).with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(label, palette.text_style()));
+                        });
+                    }
+                });
+        });
+}
+
+/// Cycles the pressed tile through empty, X and O
+#[allow(clippy::too_many_arguments)]
+fn editor_tile_pressed(
+    trigger: Trigger<Pressed>,
+    tiles: Query<&Tile>,
+    symbols: Query<(Entity, &EditorSymbol)>,
+    mut editor: ResMut<EditorData>,
+    mut texts: EditorTexts,
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok(tile) = tiles.get(trigger.entity()) else {
+        return;
+    };
+    let index = tile.index as usize;
+    let next = match editor.grid.clear_elem(index) {
+        Some(FieldStates::Empty) => FieldStates::Player1,
+        Some(FieldStates::Player1) => FieldStates::Player2,
+        _ => FieldStates::Empty,
+    };
+    editor.grid.set_elem(index, next);
+    editor.to_move = editor.expected_to_move();
+    commands.remove_resource::<EditorAnalysis>();
+
+    for (entity, symbol) in &symbols {
+        if symbol.0 == tile.index {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    if let Some(symbol) = editor.symbol_at(tile.index) {
+        spawn_symbol(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &theme,
+            tile,
+            symbol,
+            (OnEditorScreen, EditorSymbol(tile.index)),
+        );
+    }
+    refresh_texts(&editor, &mut texts, editor.status());
+}
+
+fn refresh_texts(editor: &EditorData, texts: &mut EditorTexts, status: String) {
+    for (mut text, side_to_move) in texts {
+        text.sections[0].value = if side_to_move {
+            format!("To move: {:?}", editor.to_move)
+        } else {
+            status.clone()
+        };
+    }
+}
+
+/// Chess-like name of a field, e.g. "b2" for the centre of a 3x3 board
+fn field_name(grid: &Grid, index: usize) -> String {
+    let (_, cols) = grid.size();
+    let col = (b'a' + (index % cols) as u8) as char;
+    format!("{}{}", col, index / cols + 1)
+}
+
+/// Best line for the side to move in `position` and how the game ends with it
+fn analyse(position: Classic) -> String {
+    let to_move = PlayerChoice::from_field_states(position.side_to_move()).unwrap_or_default();
+    let analysis = MiniMax::new(&position).analyse();
+    let best = analysis
+        .scores
+        .iter()
+        .map(|(_, score)| *score)
+        .max()
+        .unwrap_or_default();
+    let verdict = if best >= 1000 {
+        format!("{:?} wins", to_move)
+    } else if best <= -1000 {
+        format!("{:?} wins", to_move.opposite())
+    } else {
+        "Draw".to_string()
+    };
+    let line = analysis
+        .principal_variation
+        .iter()
+        .map(|placement| {
+            let symbol = PlayerChoice::from_field_states(placement.value)
+                .map(|symbol| format!("{:?}", symbol))
+                .unwrap_or_default();
+            format!("{}{}", symbol, field_name(position.grid(), placement.index))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{}: {}", verdict, line)
+}
+
+/// Replaces the edited position by the exported one, returning where it was
+/// read from
+fn import(editor: &mut EditorData) -> Result<PathBuf, String> {
+    let path = position_path().ok_or("There is no data directory to import from")?;
+    let text = fs::read_to_string(&path)
+        .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+    let grid = Grid::from_str(&text)?;
+    if grid.size() != editor.grid.size() {
+        return Err(format!(
+            "Expected a {}x{} board",
+            editor.grid.size().0,
+            editor.grid.size().1
+        ));
+    }
+    editor.grid = grid.with_win_length(editor.grid.win_length());
+    editor.to_move = editor.expected_to_move();
+    Ok(path)
+}
+
+/// Writes the edited position for [`import`], returning where to
+fn export(editor: &EditorData) -> Result<PathBuf, String> {
+    let path = position_path().ok_or("There is no data directory to export to")?;
+    let write = |path: &PathBuf| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, editor.grid.to_string())
+    };
+    write(&path).map_err(|error| format!("Couldn't write {}: {}", path.display(), error))?;
+    Ok(path)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn editor_action(
+    interaction_query: ButtonInteractions<EditorButtonAction>,
+    mut editor: ResMut<EditorData>,
+    mut texts: EditorTexts,
+    mut app_state: ResMut<NextState<AppState>>,
+    settings: Res<GameSettings>,
+    tiles: Query<&Tile>,
+    symbols: Query<Entity, With<EditorSymbol>>,
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            EditorButtonAction::ToggleSideToMove => {
+                editor.to_move = editor.to_move.opposite();
+                commands.remove_resource::<EditorAnalysis>();
+                refresh_texts(&editor, &mut texts, editor.status());
+            }
+            EditorButtonAction::Analyse => {
+                let status = match editor.validate() {
+                    Ok(position) if position.status() == Status::Ongoing => {
+                        commands.insert_resource(EditorAnalysis(
+                            AsyncComputeTaskPool::get().spawn(async move { analyse(position) }),
+                        ));
+                        "Analysing…".to_string()
+                    }
+                    Ok(_) => "The game is already over".to_string(),
+                    Err(error) => error,
+                };
+                refresh_texts(&editor, &mut texts, status);
+            }
+            EditorButtonAction::Import => {
+                commands.remove_resource::<EditorAnalysis>();
+                let status = match import(&mut editor) {
+                    Ok(path) => format!("Imported {}. {}", path.display(), editor.status()),
+                    Err(error) => error,
+                };
+                for entity in &symbols {
+                    commands.entity(entity).despawn_recursive();
+                }
+                for tile in &tiles {
+                    if let Some(symbol) = editor.symbol_at(tile.index) {
+                        spawn_symbol(
+                            &mut commands,
+                            &mut meshes,
+                            &mut materials,
+                            &theme,
+                            tile,
+                            symbol,
+                            (OnEditorScreen, EditorSymbol(tile.index)),
+                        );
+                    }
+                }
+                refresh_texts(&editor, &mut texts, status);
+            }
+            EditorButtonAction::Export => {
+                let status = match export(&editor) {
+                    Ok(path) => format!("Exported to {}", path.display()),
+                    Err(error) => error,
+                };
+                refresh_texts(&editor, &mut texts, status);
+            }
+            EditorButtonAction::PlayX | EditorButtonAction::PlayO => {
+                let position = match editor.validate() {
+                    Ok(position) => position,
+                    Err(error) => {
+                        refresh_texts(&editor, &mut texts, error);
+                        continue;
+                    }
+                };
+                let player = match action {
+                    EditorButtonAction::PlayX => PlayerChoice::X,
+                    _ => PlayerChoice::O,
+                };
+                info!("Playing as {:?} from the editor", player);
+                // Keeps the side to move picked in the editor
+                commands.insert_resource(GameData {
+                    position: Position::Classic(position),
+                    ..GameData::new(
+                        editor.grid.clone(),
+                        player,
+                        Variant::Classic,
+                        settings.mode,
+                        settings.engines,
+                    )
+                });
+                app_state.set(AppState::InGame);
+            }
+            EditorButtonAction::Back => {
+                app_state.set(AppState::InMenu);
+            }
+        }
+    }
+}
+
+/// Shows the analysis once its search has finished
+fn poll_analysis(
+    mut analysis: ResMut<EditorAnalysis>,
+    editor: Res<EditorData>,
+    mut texts: EditorTexts,
+    mut commands: Commands,
+) {
+    let Some(status) = block_on(future::poll_once(&mut analysis.0)) else {
+        return;
+    };
+    commands.remove_resource::<EditorAnalysis>();
+    refresh_texts(&editor, &mut texts, status);
+}
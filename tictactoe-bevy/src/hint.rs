@@ -0,0 +1,449 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    sprite::MaterialMesh2dBundle,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use tictactoe_logic::{
+    minimax::{Analysis, MiniMax},
+    rules::Placement,
+};
+
+use crate::{
+    position::Position,
+    theme::{Theme, ThemeColor},
+    AppState, ButtonInteractions, GameData, Mode, OnGameScreen, PlayerChoice, Tile,
+};
+
+pub struct HintPlugin;
+
+/// How long the recommended tile keeps pulsing
+const PULSE_SECONDS: f32 = 2.0;
+
+/// Options of the hint buttons, kept between games
+#[derive(Resource, Default)]
+struct HintSettings {
+    /// Tint every empty tile by the outcome of playing there
+    show_all: bool,
+}
+
+impl HintSettings {
+    fn label(&self) -> String {
+        format!("Show all: {}", if self.show_all { "on" } else { "off" })
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum HintButtonAction {
+    Hint,
+    ShowAll,
+}
+
+#[derive(Component)]
+struct ShowAllText;
+
+/// Overlay pulsing on the tile the engine recommends
+#[derive(Component)]
+struct HintPulse {
+    timer: Timer,
+    size: f32,
+    /// Moves played when the hint was given, the pulse stops once the
+    /// position changes
+    moves: u32,
+}
+
+/// Overlay tinting a tile by the outcome of playing there
+#[derive(Component)]
+struct OutcomeTint;
+
+/// Search for the hint button running on the [`AsyncComputeTaskPool`]
+#[derive(Resource)]
+struct HintTask {
+    task: Task<Option<Placement>>,
+    /// Number of moves played when the search started, the hint is dropped
+    /// once the position changed
+    moves: u32,
+}
+
+/// Search for the outcome tints, replaced whenever the position changes
+#[derive(Resource)]
+struct OutcomeTask {
+    task: Task<Analysis<Placement>>,
+    moves: u32,
+}
+
+/// What playing on a tile leads to with best play from both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    fn from_score(score: i32) -> Self {
+        if score >= 1000 {
+            Outcome::Win
+        } else if score <= -1000 {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Color {
+        let good = match self {
+            Outcome::Win => Some(true),
+            Outcome::Draw => None,
+            Outcome::Loss => Some(false),
+        };
+        theme.outcome(good).with_alpha(0.5)
+    }
+}
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HintSettings>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                setup_hints.run_if(|game_data: Option<Res<GameData>>| {
+                    game_data.is_some_and(|data| data.mode != Mode::Spectate)
+                }),
+            )
+            .add_systems(
+                Update,
+                (
+                    hint_action,
+                    poll_hint.run_if(resource_exists::<HintTask>),
+                    pulse_hints,
+                    update_outcome_tints,
+                    poll_outcome_tints.run_if(resource_exists::<OutcomeTask>),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_exists::<GameData>),
+            )
+            .add_systems(OnExit(AppState::InGame), |mut commands: Commands| {
+                commands.remove_resource::<HintTask>();
+                commands.remove_resource::<OutcomeTask>();
+            });
+    }
+}
+
+fn setup_hints(mut commands: Commands, settings: Res<HintSettings>, theme: Res<Theme>) {
+    let palette = theme.palette();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(60.0),
+                    column_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for action in [HintButtonAction::Hint, HintButtonAction::ShowAll] {
+                parent
+                    .spawn((palette.button_bundle(), action))
+                    .with_children(|parent| match action {
+                        HintButtonAction::Hint => {
+                            parent.spawn(TextBundle::from_section("Hint", palette.text_style()));
+                        }
+                        HintButtonAction::ShowAll => {
+                            parent.spawn((
+                                TextBundle::from_section(settings.label(), palette.text_style()),
+                                ShowAllText,
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+/// Move the engine recommends to the side to move
+fn best_move(position: &Position) -> Option<Placement> {
+    MiniMax::new(position).best_move()
+}
+
+/// Engine's view of every move for the side to move, a search per move
+fn analyse(position: &Position) -> Analysis<Placement> {
+    MiniMax::new(position).analyse()
+}
+
+fn spawn_hint(game_data: &GameData) -> Task<Option<Placement>> {
+    let position = game_data.position.clone();
+    AsyncComputeTaskPool::get().spawn(async move { best_move(&position) })
+}
+
+fn spawn_analysis(game_data: &GameData) -> Task<Analysis<Placement>> {
+    let position = game_data.position.clone();
+    AsyncComputeTaskPool::get().spawn(async move { analyse(&position) })
+}
+
+/// Symbol to place with the recommended move, only worth showing when the
+/// side to move has the choice
+fn hint_symbol(game_data: &GameData, best_move: Placement) -> Option<PlayerChoice> {
+    if !game_data.position.picks_symbol() {
+        return None;
+    }
+    PlayerChoice::from_field_states(best_move.value)
+}
+
+/// Best outcome of every empty tile, in wild games over both symbols
+fn outcomes(analysis: &Analysis<Placement>) -> Vec<(u32, Outcome)> {
+    let mut outcomes: Vec<(u32, Outcome)> = Vec::new();
+    for (placement, score) in &analysis.scores {
+        let index = placement.index as u32;
+        let outcome = Outcome::from_score(*score);
+        match outcomes.iter_mut().find(|(tile, _)| *tile == index) {
+            Some((_, best)) => *best = (*best).max(outcome),
+            None => outcomes.push((index, outcome)),
+        }
+    }
+    outcomes
+}
+
+/// Whether the human may ask for help right now
+fn hints_available(game_data: &GameData) -> bool {
+    !game_data.won && !game_data.is_computer_turn()
+}
+
+fn hint_action(
+    interaction_query: ButtonInteractions<HintButtonAction>,
+    game_data: Res<GameData>,
+    hint_task: Option<Res<HintTask>>,
+    mut settings: ResMut<HintSettings>,
+    mut texts: Query<&mut Text, With<ShowAllText>>,
+    mut commands: Commands,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            HintButtonAction::Hint => {
+                let running = hint_task
+                    .as_ref()
+                    .is_some_and(|t| t.moves == game_data.moves);
+                if running || !hints_available(&game_data) {
+                    continue;
+                }
+                commands.insert_resource(HintTask {
+                    task: spawn_hint(&game_data),
+                    moves: game_data.moves,
+                });
+            }
+            HintButtonAction::ShowAll => {
+                settings.show_all = !settings.show_all;
+                for mut text in &mut texts {
+                    text.sections[0].value = settings.label();
+                }
+            }
+        }
+    }
+}
+
+/// Lets the recommended tile pulse once the search has finished, in wild
+/// games together with the symbol to place there
+#[allow(clippy::too_many_arguments)]
+fn poll_hint(
+    mut hint_task: ResMut<HintTask>,
+    mut game_data: ResMut<GameData>,
+    tiles: Query<&Tile>,
+    pulses: Query<Entity, With<HintPulse>>,
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(best_move) = block_on(future::poll_once(&mut hint_task.task)) else {
+        return;
+    };
+    commands.remove_resource::<HintTask>();
+    if hint_task.moves != game_data.moves || !hints_available(&game_data) {
+        return;
+    }
+    let Some(best_move) = best_move else {
+        return;
+    };
+    let Some(tile) = tiles
+        .iter()
+        .find(|tile| tile.index == best_move.index as u32)
+    else {
+        return;
+    };
+    game_data.hints += 1;
+    for entity in &pulses {
+        commands.entity(entity).despawn_recursive();
+    }
+    let mut pulse = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(Rectangle::default()).into(),
+            transform: Transform::default()
+                .with_scale(Vec3::splat(tile.size))
+                .with_translation(tile.pos.extend(0.15)),
+            material: materials.add(theme.palette().highlight.with_alpha(0.5)),
+            ..default()
+        },
+        ThemeColor::Highlight,
+        HintPulse {
+            timer: Timer::from_seconds(PULSE_SECONDS, TimerMode::Once),
+            size: tile.size,
+            moves: game_data.moves,
+        },
+        OnGameScreen,
+    ));
+    if let Some(symbol) = hint_symbol(&game_data, best_move) {
+        // Undoes the scale of the overlay, so the label keeps its font size
+        // and only pulses along
+        pulse.with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    format!("{:?}", symbol),
+                    TextStyle {
+                        font_size: tile.size / 2.0,
+                        color: theme.symbol(symbol),
+                        ..Default::default()
+                    },
+                ),
+                transform: Transform::from_xyz(0.0, 0.0, 0.01)
+                    .with_scale(Vec3::splat(1.0 / tile.size)),
+                ..default()
+            });
+        });
+    }
+}
+
+/// Lets the hinted tile's overlay grow and shrink until its time is up or a
+/// move has been played
+fn pulse_hints(
+    time: Res<Time>,
+    game_data: Res<GameData>,
+    mut pulses: Query<(Entity, &mut HintPulse, &mut Transform)>,
+    mut commands: Commands,
+) {
+    for (entity, mut pulse, mut transform) in &mut pulses {
+        if pulse.timer.tick(time.delta()).finished() || pulse.moves != game_data.moves {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let phase = (pulse.timer.elapsed_secs() * 2.0 * TAU).sin();
+        transform.scale = Vec3::splat(pulse.size * (0.8 + 0.2 * phase));
+    }
+}
+
+/// Keeps the outcome tints in line with the position, they are only shown
+/// while a human is to move
+fn update_outcome_tints(
+    settings: Res<HintSettings>,
+    game_data: Res<GameData>,
+    theme: Res<Theme>,
+    tints: Query<Entity, With<OutcomeTint>>,
+    outcome_task: Option<Res<OutcomeTask>>,
+    mut shown: Local<Option<(u32, bool, bool)>>,
+    mut commands: Commands,
+) {
+    let visible = settings.show_all && hints_available(&game_data);
+    // The search is too costly to repeat every frame
+    let key = (game_data.moves, game_data.won, visible);
+    let pending = !tints.is_empty() || outcome_task.is_some();
+    if *shown == Some(key) && pending == visible && !theme.is_changed() {
+        return;
+    }
+    *shown = Some(key);
+    for entity in &tints {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<OutcomeTask>();
+    if visible {
+        commands.insert_resource(OutcomeTask {
+            task: spawn_analysis(&game_data),
+            moves: game_data.moves,
+        });
+    }
+}
+
+/// Tints the tiles once their search has finished
+fn poll_outcome_tints(
+    mut outcome_task: ResMut<OutcomeTask>,
+    game_data: Res<GameData>,
+    theme: Res<Theme>,
+    tiles: Query<&Tile>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(analysis) = block_on(future::poll_once(&mut outcome_task.task)) else {
+        return;
+    };
+    commands.remove_resource::<OutcomeTask>();
+    if outcome_task.moves != game_data.moves {
+        return;
+    }
+    for (index, outcome) in outcomes(&analysis) {
+        let Some(tile) = tiles.iter().find(|tile| tile.index == index) else {
+            continue;
+        };
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(Rectangle::default()).into(),
+                transform: Transform::default()
+                    .with_scale(Vec3::splat(tile.size))
+                    .with_translation(tile.pos.extend(0.12)),
+                material: materials.add(outcome.color(&theme)),
+                ..default()
+            },
+            OutcomeTint,
+            OnGameScreen,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    #[test]
+    fn only_the_block_avoids_a_loss() {
+        let game_data = GameData::new(
+            "1 0 2\n0 1 0\n0 0 0".parse().unwrap(),
+            PlayerChoice::O,
+            Variant::Classic,
+            Mode::Computer,
+            Default::default(),
+        );
+        let hint = best_move(&game_data.position).unwrap();
+        assert_eq!(hint.index, 8);
+        for (index, outcome) in outcomes(&analyse(&game_data.position)) {
+            let expected = if index == 8 {
+                Outcome::Draw
+            } else {
+                Outcome::Loss
+            };
+            assert_eq!(outcome, expected, "tile {}", index);
+        }
+        assert_eq!(hint_symbol(&game_data, hint), None);
+    }
+
+    #[test]
+    fn wild_hints_name_the_symbol_to_place() {
+        // O completes the top row for X
+        let game_data = GameData::new(
+            "2 2 0\n1 0 0\n0 1 0".parse().unwrap(),
+            PlayerChoice::X,
+            Variant::Wild,
+            Mode::Computer,
+            Default::default(),
+        );
+        let hint = best_move(&game_data.position).unwrap();
+        assert_eq!(hint.index, 2);
+        assert_eq!(hint_symbol(&game_data, hint), Some(PlayerChoice::O));
+    }
+}
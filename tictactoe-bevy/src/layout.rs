@@ -0,0 +1,286 @@
+use bevy::{prelude::*, window::WindowResized};
+
+use crate::{theme::Theme, AppState, GameData, Tile, WinPossibilities};
+
+/// Distance between tile centres in world units. The camera zooms so the
+/// board fills the window whatever its size.
+const STEP: f32 = 128.0;
+
+/// Share of a cell covered by its tile, the rest shows as grid lines
+const TILE_FILL: f32 = 0.94;
+
+/// Where the board of the current screen lies in the world. Tiles are placed
+/// from it, clicks are mapped back to tiles through it and the camera is
+/// fitted to it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct BoardLayout {
+    pub rows: usize,
+    pub cols: usize,
+    /// Distance between the centres of neighbouring tiles
+    pub step: f32,
+    pub center: Vec2,
+}
+
+impl BoardLayout {
+    /// Board of the given size centred on the world origin
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            step: STEP,
+            center: Vec2::ZERO,
+        }
+    }
+
+    /// Width and height of the whole board in world units
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.cols as f32, self.rows as f32) * self.step
+    }
+
+    /// Edge length of a tile, a little less than the step
+    pub fn tile_size(&self) -> f32 {
+        self.step * TILE_FILL
+    }
+
+    /// Centre of the tile with the given index. Tile 0 is the top left one,
+    /// indices grow to the right and then downwards.
+    pub fn tile_center(&self, index: u32) -> Vec2 {
+        let row = (index as usize / self.cols) as f32;
+        let col = (index as usize % self.cols) as f32;
+        self.center
+            + Vec2::new(
+                (col - (self.cols - 1) as f32 / 2.0) * self.step,
+                ((self.rows - 1) as f32 / 2.0 - row) * self.step,
+            )
+    }
+
+    /// Tile whose cell contains the world position `pos`, cells include half
+    /// of the grid lines around their tile
+    pub fn tile_at(&self, pos: Vec2) -> Option<u32> {
+        let offset = (pos - self.center) / self.step;
+        let col = (offset.x + self.cols as f32 / 2.0).floor();
+        let row = (self.rows as f32 / 2.0 - offset.y).floor();
+        if col < 0.0 || row < 0.0 || col >= self.cols as f32 || row >= self.rows as f32 {
+            return None;
+        }
+        Some((row as usize * self.cols + col as usize) as u32)
+    }
+}
+
+/// Room kept free for the texts and buttons around the board, in logical
+/// pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Margins {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+}
+
+impl Margins {
+    /// Landscape windows keep the panels beside the board, portrait ones such
+    /// as phones above and below it
+    fn for_window(size: Vec2) -> Self {
+        if size.y > size.x {
+            Self {
+                left: 10.0,
+                right: 10.0,
+                top: 150.0,
+                bottom: 150.0,
+            }
+        } else {
+            Self {
+                left: 230.0,
+                right: 230.0,
+                top: 60.0,
+                bottom: 130.0,
+            }
+        }
+    }
+}
+
+/// Zoom and position of a camera that shows the whole board centred in the
+/// space the margins leave free in a window of the given logical size
+fn camera_fit(layout: &BoardLayout, window: Vec2) -> (f32, Vec2) {
+    let margins = Margins::for_window(window);
+    let free = Vec2::new(
+        window.x - margins.left - margins.right,
+        window.y - margins.top - margins.bottom,
+    )
+    .max(Vec2::ONE);
+    let scale = (layout.size() / free).max_element();
+    // Screen coordinates grow downwards, world coordinates upwards
+    let offset = Vec2::new(margins.right - margins.left, margins.top - margins.bottom) / 2.0;
+    (scale, layout.center + offset * scale)
+}
+
+/// Zooms the camera onto the board whenever a board is set up or the window
+/// changes size
+pub fn fit_camera(
+    layout: Res<BoardLayout>,
+    windows: Query<&Window>,
+    mut cameras: Query<(&mut OrthographicProjection, &mut Transform), With<Camera>>,
+) {
+    let window = windows.single();
+    let (scale, position) = camera_fit(&layout, Vec2::new(window.width(), window.height()));
+    for (mut projection, mut transform) in &mut cameras {
+        projection.scale = scale;
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+/// Run condition of [`fit_camera`]
+pub fn board_needs_fitting(
+    layout: Option<Res<BoardLayout>>,
+    mut resized: EventReader<WindowResized>,
+) -> bool {
+    let resized = resized.read().count() > 0;
+    layout.is_some_and(|layout| layout.is_changed() || resized)
+}
+
+/// World position under the mouse, taking the camera's position and zoom
+/// into account
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.origin.truncate())
+}
+
+/// Tile under the mouse, if it may be played on. Every tile of the editor
+/// may be pressed, in a game only the empty ones.
+#[derive(Resource, Default)]
+pub struct HoveredTile(pub Option<u32>);
+
+/// Lights up the tile under the mouse while it may be played on, until the
+/// game is decided
+#[allow(clippy::too_many_arguments)]
+pub fn hover_tiles(
+    layout: Res<BoardLayout>,
+    windows: Query<&Window>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    tiles: Query<(&Tile, &Handle<ColorMaterial>)>,
+    new_tiles: Query<(), Added<Tile>>,
+    win: Option<Res<WinPossibilities>>,
+    state: Res<State<AppState>>,
+    game_data: Option<Res<GameData>>,
+    theme: Res<Theme>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut hovered: ResMut<HoveredTile>,
+) {
+    let (camera, camera_transform) = camera.single();
+    let in_game = *state.get() == AppState::InGame;
+    let now = cursor_world_position(windows.single(), camera, camera_transform)
+        .and_then(|pos| layout.tile_at(pos))
+        .filter(|_| win.is_none())
+        .filter(|index| {
+            !in_game
+                || game_data
+                    .as_ref()
+                    .is_none_or(|game_data| game_data.symbol_at(*index).is_none())
+        });
+    if now == hovered.0 && new_tiles.is_empty() {
+        return;
+    }
+    hovered.0 = now;
+    for (tile, material) in &tiles {
+        if let Some(material) = materials.get_mut(material) {
+            material.color = theme.tile(Some(tile.index) == now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(rows: usize, cols: usize) -> BoardLayout {
+        BoardLayout {
+            rows,
+            cols,
+            step: 100.0,
+            center: Vec2::new(10.0, -20.0),
+        }
+    }
+
+    /// Corners of the board as seen on screen, in logical pixels from the top
+    /// left corner of the window
+    fn board_on_screen(layout: &BoardLayout, window: Vec2) -> (Vec2, Vec2) {
+        let (scale, camera) = camera_fit(layout, window);
+        let to_screen = |world: Vec2| {
+            let relative = (world - camera) / scale;
+            window / 2.0 + Vec2::new(relative.x, -relative.y)
+        };
+        let half = layout.size() / 2.0;
+        (
+            to_screen(layout.center + Vec2::new(-half.x, half.y)),
+            to_screen(layout.center + Vec2::new(half.x, -half.y)),
+        )
+    }
+
+    #[test]
+    fn board_fills_the_free_space_of_any_window() {
+        for window in [
+            Vec2::new(1280.0, 720.0),
+            Vec2::new(3840.0, 2160.0),
+            Vec2::new(390.0, 844.0),
+        ] {
+            let margins = Margins::for_window(window);
+            for layout in [BoardLayout::new(3, 3), BoardLayout::new(4, 7), layout(6, 3)] {
+                let (top_left, bottom_right) = board_on_screen(&layout, window);
+                assert!(top_left.x >= margins.left - 0.01, "{:?}", window);
+                assert!(top_left.y >= margins.top - 0.01, "{:?}", window);
+                assert!(bottom_right.x <= window.x - margins.right + 0.01);
+                assert!(bottom_right.y <= window.y - margins.bottom + 0.01);
+                // Centred, and touching the margins on at least one axis
+                let gaps = (
+                    top_left.x - margins.left,
+                    window.x - margins.right - bottom_right.x,
+                    top_left.y - margins.top,
+                    window.y - margins.bottom - bottom_right.y,
+                );
+                assert!((gaps.0 - gaps.1).abs() < 0.01);
+                assert!((gaps.2 - gaps.3).abs() < 0.01);
+                assert!(gaps.0.min(gaps.2) < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn every_tile_centre_maps_back_to_its_tile() {
+        for (rows, cols) in [(3, 3), (4, 6), (7, 5)] {
+            let layout = layout(rows, cols);
+            for index in 0..(rows * cols) as u32 {
+                assert_eq!(layout.tile_at(layout.tile_center(index)), Some(index));
+            }
+        }
+    }
+
+    #[test]
+    fn corners_belong_to_their_tile() {
+        let layout = layout(3, 4);
+        let centre = layout.tile_center(5);
+        for corner in [
+            Vec2::new(-49.0, 49.0),
+            Vec2::new(49.0, 49.0),
+            Vec2::new(-49.0, -49.0),
+            Vec2::new(49.0, -49.0),
+        ] {
+            assert_eq!(layout.tile_at(centre + corner), Some(5));
+        }
+    }
+
+    #[test]
+    fn points_beside_the_board_hit_nothing() {
+        let layout = layout(3, 4);
+        let left = layout.tile_center(4) - Vec2::new(51.0, 0.0);
+        let below = layout.tile_center(9) - Vec2::new(0.0, 51.0);
+        assert_eq!(layout.tile_at(left), None);
+        assert_eq!(layout.tile_at(below), None);
+        assert_eq!(layout.tile_at(left + Vec2::new(2.0, 0.0)), Some(4));
+    }
+}
@@ -1,28 +1,55 @@
+use animation::{AnimationPlugin, Drawing, FadeIn};
+use audio::{PlaySound, Sound, SoundPlugin};
 use bevy::{
-    color::palettes::{
-        css::{BLACK, WHITE},
-        tailwind::{BLUE_400, BLUE_800, BLUE_900, GRAY_50},
-    },
     prelude::*,
     sprite::MaterialMesh2dBundle,
-    utils::{HashMap, HashSet},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use config::ConfigPlugin;
+use editor::EditorPlugin;
+use hint::HintPlugin;
+use layout::{
+    board_needs_fitting, cursor_world_position, fit_camera, hover_tiles, BoardLayout, HoveredTile,
 };
 use menu::MenuPlugin;
+use navigation::NavigationPlugin;
+use position::Position;
+use replay::ReplayPlugin;
+use save::SavePlugin;
+use scoreboard::{MatchScore, ScoreboardPlugin};
+use serde::{Deserialize, Serialize};
+use settings::SettingsPlugin;
+use spectator::SpectatorPlugin;
+use stats::StatsPlugin;
+use theme::{Palette, Theme, ThemeColor, ThemePlugin};
 use tictactoe_logic::{
     grid::{FieldStates, Grid},
     minimax::MiniMax,
+    rules::{empty_fields, random_move, Placement, Rules, Status},
 };
+mod animation;
+mod audio;
+mod config;
+mod editor;
+mod hint;
+mod layout;
 mod menu;
-#[derive(Event)]
-struct Click(pub Vec2, pub f32);
-
+mod navigation;
+mod position;
+mod replay;
+mod save;
+mod scoreboard;
+mod settings;
+mod spectator;
+mod stats;
+mod theme;
 #[derive(Event)]
 struct Pressed;
 
 #[derive(Event)]
 struct KIMove;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct Tile {
     pos: Vec2,
     size: f32,
@@ -33,13 +60,146 @@ pub enum AppState {
     #[default]
     InMenu,
     InGame,
+    InEditor,
+    InSettings,
+    InStats,
+    InReplay,
 }
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
 pub enum PlayerChoice {
+    #[default]
     X,
     O,
 }
 
+#[derive(
+    Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Variant {
+    #[default]
+    Classic,
+    /// Either side may place either symbol, whoever completes a line wins
+    Wild,
+}
+
+impl Variant {
+    pub fn next(&self) -> Self {
+        match self {
+            Variant::Classic => Variant::Wild,
+            Variant::Wild => Variant::Classic,
+        }
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variant::Classic => write!(f, "Classic"),
+            Variant::Wild => write!(f, "Wild"),
+        }
+    }
+}
+
+/// Who plays against whom
+#[derive(
+    Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Mode {
+    #[default]
+    Computer,
+    /// Two humans taking turns on the same screen
+    Hotseat,
+    /// Two engines playing each other while the humans watch
+    Spectate,
+}
+
+impl Mode {
+    pub fn next(&self) -> Self {
+        match self {
+            Mode::Computer => Mode::Hotseat,
+            Mode::Hotseat => Mode::Spectate,
+            Mode::Spectate => Mode::Computer,
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Computer => write!(f, "Human vs Computer"),
+            Mode::Hotseat => write!(f, "Human vs Human"),
+            Mode::Spectate => write!(f, "Computer vs Computer"),
+        }
+    }
+}
+
+/// How a computer player picks its moves
+#[derive(
+    Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Engine {
+    #[default]
+    MiniMax,
+    /// Plays any legal move, for an opponent that is easy to beat
+    Random,
+}
+
+impl Engine {
+    pub fn next(&self) -> Self {
+        match self {
+            Engine::MiniMax => Engine::Random,
+            Engine::Random => Engine::MiniMax,
+        }
+    }
+}
+
+impl std::fmt::Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::MiniMax => write!(f, "MiniMax"),
+            Engine::Random => write!(f, "Random"),
+        }
+    }
+}
+
+/// Deepest search the menu offers, enough to play a 3x3 board perfectly
+pub const MAX_DEPTH: i8 = 9;
+
+/// Engine of one side and how many moves ahead it looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineSettings {
+    pub engine: Engine,
+    pub depth: i8,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            engine: Engine::default(),
+            depth: MAX_DEPTH,
+        }
+    }
+}
+
+impl EngineSettings {
+    /// Move this engine plays for the side to move in `position`. Large boards
+    /// keep their own depth limit when it is shallower than the chosen one.
+    fn pick<R: Rules>(&self, position: R) -> Option<R::Move> {
+        match self.engine {
+            Engine::MiniMax => {
+                let depth = position
+                    .default_depth()
+                    .map_or(self.depth, |depth| depth.min(self.depth));
+                MiniMax::new(&position).with_depth(depth).best_move()
+            }
+            Engine::Random => random_move(&position, &mut rand::thread_rng()),
+        }
+    }
+}
+
 pub enum MoveResult {
     Won(u32),
     Moved(u32),
@@ -58,14 +218,57 @@ impl PlayerChoice {
             PlayerChoice::O => FieldStates::Player2,
         }
     }
+    pub fn from_field_states(state: FieldStates) -> Option<Self> {
+        match state {
+            FieldStates::Player1 => Some(PlayerChoice::X),
+            FieldStates::Player2 => Some(PlayerChoice::O),
+            FieldStates::Empty => None,
+        }
+    }
 }
 #[derive(Resource)]
 pub struct GameData {
     pub player: PlayerChoice,
-    pub grid: Grid,
+    /// Board and side to move, under the rules of [`GameData::variant`]
+    pub position: Position,
     pub moves: u32,
     pub won: bool,
+    pub variant: Variant,
+    /// Symbol the human places next, only used where the rules let them pick
+    pub symbol: PlayerChoice,
+    pub mode: Mode,
+    /// Engines playing X and O whenever the computer is to move
+    pub engines: [EngineSettings; 2],
+    /// Moves played since the game started, oldest first
+    pub history: Vec<Placement>,
+    /// Moves taken back, the next one to redo last
+    pub undone: Vec<Placement>,
+    /// Number of times the engine's advice was asked for this game
+    pub hints: u32,
+}
+/// Everything the engine needs to find the computer's next move, detached
+/// from [`GameData`] so the search can run on another thread
+pub struct KiSearch {
+    position: Position,
+    engine: EngineSettings,
+}
+
+impl KiSearch {
+    pub fn run(self) -> Option<Placement> {
+        self.engine.pick(self.position)
+    }
+}
+
+/// Search running on the [`AsyncComputeTaskPool`], dropping it cancels the
+/// search
+#[derive(Resource)]
+struct KiTask {
+    task: Task<Option<Placement>>,
+    /// Number of moves played when the search started, a result for any
+    /// other position is thrown away
+    moves: u32,
 }
+
 #[derive(Debug, PartialEq, Eq, Resource)]
 pub enum WinPossibilities {
     XWon,
@@ -88,105 +291,231 @@ impl std::fmt::Display for WinPossibilities {
 #[derive(Debug, PartialEq, Eq, Component)]
 pub enum ButtonAction {
     PlayAgain,
+    /// Starts the next game of a best-of-N match
+    NextGame,
 }
 
-impl GameData {
-    pub fn make_move(&mut self, grid_index: u32) -> Result<MoveResult, ()> {
-        let moves = self.who_moves();
-        if moves != self.player {
-            return Err(());
-        }
-        let current_state = moves.to_field_states();
+/// Buttons tagged with an `A` whose interaction changed since the last run
+pub type ButtonInteractions<'w, 's, A> =
+    Query<'w, 's, (&'static Interaction, &'static A), (Changed<Interaction>, With<Button>)>;
 
-        // Try to make the move
-        if self
-            .grid
-            .set_elem(grid_index as usize, current_state)
-            .is_some()
-        {
-            self.moves += 1;
+#[derive(Component)]
+struct SymbolPicker;
 
-            // Check if the current move resulted in a win
-            if self.grid.check_win(current_state) {
-                self.won = true;
-                Ok(MoveResult::Won(grid_index))
-            } else {
-                Ok(MoveResult::Moved(grid_index))
-            }
-        } else {
-            Err(())
-        }
-    }
+#[derive(Component, Clone, Copy)]
+enum TakebackAction {
+    Undo,
+    Redo,
+}
 
-    pub fn who_moves(&self) -> PlayerChoice {
-        // If moves is even, X moves; if odd, O moves
-        if self.moves % 2 == 0 {
+#[derive(Component)]
+struct TurnText;
+
+#[derive(Component)]
+struct SymbolPickerText;
+
+impl GameData {
+    /// Game on `grid` that nobody has moved in yet. Symbols already on the
+    /// board, set up in the editor, count as moves played.
+    pub fn new(
+        grid: Grid,
+        player: PlayerChoice,
+        variant: Variant,
+        mode: Mode,
+        engines: [EngineSettings; 2],
+    ) -> Self {
+        let (rows, cols) = grid.size();
+        let moves = (rows * cols - empty_fields(&grid).len()) as u32;
+        // A game X opened, so O moves after an odd number of symbols
+        let to_move = if moves.is_multiple_of(2) {
             PlayerChoice::X
         } else {
             PlayerChoice::O
+        };
+        Self {
+            player,
+            position: variant.position(grid, to_move),
+            moves,
+            won: false,
+            variant,
+            symbol: player,
+            mode,
+            engines,
+            history: Vec::new(),
+            undone: Vec::new(),
+            hints: 0,
         }
     }
-    pub fn make_ki_move(&mut self) -> Result<MoveResult, ()> {
-        let mut minimax = MiniMax::new(&self.grid);
-        let new_grid = self.grid.clone();
-        self.grid = minimax.calculate(self.player.opposite().to_field_states());
-        self.moves += 1;
-        let mut changed_index = 0;
-        let mut same = true;
-        for (index, (old, new)) in self
-            .grid
-            .clone()
-            .into_iter()
-            .zip(new_grid.into_iter())
-            .enumerate()
-        {
-            if old != new {
-                // Found the move, now make it through the regular move system
-                changed_index = index as u32;
-                same = false;
-            }
+
+    pub fn make_move(&mut self, grid_index: u32) -> Result<MoveResult, ()> {
+        if self.won || self.is_computer_turn() {
+            return Err(());
         }
-        if same {
+        // The picked symbol where the rules leave the choice to the player
+        let moves: Vec<Placement> = self
+            .position
+            .legal_moves()
+            .into_iter()
+            .filter(|placement| placement.index == grid_index as usize)
+            .collect();
+        let placement = moves
+            .iter()
+            .find(|placement| placement.value == self.symbol.to_field_states())
+            .or(moves.first())
+            .copied()
+            .ok_or(())?;
+        let result = self.place(placement)?;
+        self.undone.clear();
+        Ok(result)
+    }
+
+    /// Plays `placement` for the side to move, if the rules allow it
+    fn place(&mut self, placement: Placement) -> Result<MoveResult, ()> {
+        if !self.position.legal_moves().contains(&placement) {
             return Err(());
         }
-        // Check if KI won
-        if self.grid.check_win(FieldStates::Player2) {
+        self.position.apply(placement);
+        self.moves += 1;
+        self.history.push(placement);
+        let index = placement.index as u32;
+        if matches!(self.position.status(), Status::Won(_)) {
             self.won = true;
-            Ok(MoveResult::Won(changed_index))
+            Ok(MoveResult::Won(index))
         } else {
-            Ok(MoveResult::Moved(changed_index))
+            Ok(MoveResult::Moved(index))
         }
     }
-    pub fn check_game_state(&mut self) -> WinPossibilities {
-        // Check if X won
-        if self.grid.check_win(PlayerChoice::X.to_field_states()) {
-            self.won = true;
-            return WinPossibilities::XWon;
+
+    pub fn grid(&self) -> &Grid {
+        self.position.grid()
+    }
+
+    /// Symbol standing on the given tile, if any
+    pub fn symbol_at(&self, grid_index: u32) -> Option<PlayerChoice> {
+        self.grid()
+            .get_elem(grid_index as usize)
+            .and_then(|state| PlayerChoice::from_field_states(*state))
+    }
+
+    pub fn who_moves(&self) -> PlayerChoice {
+        PlayerChoice::from_field_states(self.position.side_to_move()).unwrap_or(PlayerChoice::X)
+    }
+    /// Whether the side to move is played by the computer. In hotseat games
+    /// the humans play both sides, spectators neither.
+    pub fn is_computer_turn(&self) -> bool {
+        match self.mode {
+            Mode::Computer => self.who_moves() != self.player,
+            Mode::Hotseat => false,
+            Mode::Spectate => true,
         }
+    }
 
-        // Check if O won
-        if self.grid.check_win(PlayerChoice::O.to_field_states()) {
-            self.won = true;
-            return WinPossibilities::OWon;
+    /// Engine configured for the given side
+    pub fn engine(&self, side: PlayerChoice) -> EngineSettings {
+        self.engines[side as usize]
+    }
+
+    /// Search for the computer's reply in the current position
+    pub fn ki_search(&self) -> KiSearch {
+        KiSearch {
+            position: self.position.clone(),
+            engine: self.engine(self.who_moves()),
         }
+    }
 
-        // Check for tie (grid is full)
-        if self.grid.is_full() {
-            self.won = true;
-            return WinPossibilities::Tie;
+    /// Plays a move found by [`KiSearch::run`] for the side to move
+    pub fn make_ki_move(&mut self, best_move: Placement) -> Result<MoveResult, ()> {
+        if self.won || !self.is_computer_turn() {
+            return Err(());
         }
+        let result = self.place(best_move)?;
+        self.undone.clear();
+        Ok(result)
+    }
 
-        // Game is still ongoing
-        WinPossibilities::None
+    /// Takes back the last moves up to and including the last one a human
+    /// made, returns the fields that were emptied
+    pub fn undo(&mut self) -> Vec<u32> {
+        let mut undone = Vec::new();
+        while let Some(placement) = self.history.pop() {
+            self.position.undo(placement);
+            self.moves -= 1;
+            undone.push(placement);
+            if !self.is_computer_turn() {
+                break;
+            }
+        }
+        if self.is_computer_turn() {
+            // Only the computer has moved so far, put its moves back
+            while let Some(placement) = undone.pop() {
+                let _ = self.place(placement);
+            }
+            return Vec::new();
+        }
+        if !undone.is_empty() {
+            self.won = false;
+        }
+        let fields = undone
+            .iter()
+            .map(|placement| placement.index as u32)
+            .collect();
+        self.undone.extend(undone);
+        fields
+    }
+
+    /// Plays the moves taken back by [`GameData::undo`] again, up to the next
+    /// human turn, returns the fields that were filled
+    pub fn redo(&mut self) -> Vec<u32> {
+        let mut redone = Vec::new();
+        while let Some(placement) = self.undone.pop() {
+            if self.place(placement).is_err() {
+                break;
+            }
+            redone.push(placement.index as u32);
+            if self.won || !self.is_computer_turn() {
+                break;
+            }
+        }
+        redone
+    }
+    /// Tiles of every completed line, in wild games of either symbol
+    pub fn winning_tiles(&self) -> Vec<u32> {
+        let mut tiles = Vec::new();
+        let grid = self.grid();
+        for line in grid.lines() {
+            let first = grid.get_elem(line[0]);
+            if first.is_some_and(|state| *state != FieldStates::Empty)
+                && line.iter().all(|&index| grid.get_elem(index) == first)
+            {
+                tiles.extend(line.iter().map(|&index| index as u32));
+            }
+        }
+        tiles.sort_unstable();
+        tiles.dedup();
+        tiles
+    }
+    pub fn check_game_state(&mut self) -> WinPossibilities {
+        let result = match self.position.status() {
+            Status::Ongoing => return WinPossibilities::None,
+            Status::Draw => WinPossibilities::Tie,
+            Status::Won(winner) => match PlayerChoice::from_field_states(winner) {
+                Some(PlayerChoice::O) => WinPossibilities::OWon,
+                _ => WinPossibilities::XWon,
+            },
+        };
+        self.won = true;
+        result
     }
 }
 
 #[derive(Component)]
 struct Symbol {
     is_x: bool,
+    /// Tile the symbol stands on
+    index: u32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct OnGameScreen;
 
 #[derive(Component)]
@@ -196,62 +525,111 @@ fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(MenuPlugin)
-        .add_systems(Startup, setup)
-        .add_systems(OnEnter(AppState::InGame), setup_game)
-        .add_systems(
-            Update,
-            handle_click
-                .run_if(in_state(AppState::InGame))
-                .run_if(not(resource_exists::<WinPossibilities>)),
-        )
-        .add_systems(
-            Update,
-            spawn_play_again.run_if(resource_added::<WinPossibilities>),
-        )
-        .add_systems(
-            Update,
-            (button_hover_system, menu_action).run_if(resource_exists::<WinPossibilities>),
-        )
-        .add_event::<Click>()
-        .add_event::<Pressed>()
-        .add_event::<KIMove>()
-        .add_systems(
-            Update,
-            (handle_ki_move).chain().run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(
-            Update,
-            detect_win_possibilities
-                .run_if(not(resource_exists::<WinPossibilities>))
-                .run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(OnExit(AppState::InGame), (despawn_screen::<OnGameScreen>))
-        .init_resource::<SpatialIndex>()
-        .init_state::<AppState>()
-        .observe(
-            |trigger: Trigger<Click>,
-             tiles: Query<&Tile>,
-             index: Res<SpatialIndex>,
-             mut commands: Commands| {
-                // You can access the trigger data via the `Observer`
-                let event = trigger.event();
-                // Access resources
-                for e in index.get_nearby(event.0) {
-                    // Run queries
-                    let mine = tiles.get(e).unwrap();
-                    if mine.pos.distance(event.0) < mine.size + event.1 {
-                        // And queue commands, including triggering additional events
-                        // Here we trigger the `Explode` event for entity `e`
-                        commands.trigger_targets(Pressed, e);
-                    }
-                }
-            },
-        )
-        .observe(on_add_tile)
-        .observe(on_remove_tile)
+        .add_plugins(EditorPlugin)
+        .add_plugins(SpectatorPlugin)
+        .add_plugins(HintPlugin)
+        .add_plugins(NavigationPlugin)
+        .add_plugins(AnimationPlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(ThemePlugin)
+        .add_plugins(GamePlugin)
+        .add_plugins(ConfigPlugin)
+        .add_plugins(SavePlugin)
+        .add_plugins(ScoreboardPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(ReplayPlugin)
         .run();
 }
 
+/// The board, the moves of both sides and the result screen
+struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup)
+            .add_systems(OnEnter(AppState::InGame), setup_game)
+            .add_systems(
+                Update,
+                handle_click
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::InEditor)))
+                    .run_if(not(resource_exists::<WinPossibilities>)),
+            )
+            .init_resource::<HoveredTile>()
+            .add_systems(Update, fit_camera.run_if(board_needs_fitting))
+            .add_systems(
+                Update,
+                hover_tiles
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::InEditor)))
+                    .run_if(resource_exists::<BoardLayout>),
+            )
+            .add_systems(
+                Update,
+                spawn_play_again.run_if(resource_added::<WinPossibilities>),
+            )
+            .add_systems(
+                Update,
+                menu_action.run_if(resource_exists::<WinPossibilities>),
+            )
+            .add_systems(
+                Update,
+                button_hover_system.run_if(
+                    in_state(AppState::InGame)
+                        .or_else(in_state(AppState::InEditor))
+                        .or_else(in_state(AppState::InSettings))
+                        .or_else(in_state(AppState::InStats))
+                        .or_else(in_state(AppState::InReplay)),
+                ),
+            )
+            .add_systems(
+                Update,
+                pick_symbol
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(not(resource_exists::<WinPossibilities>))
+                    .run_if(|game_data: Option<Res<GameData>>| {
+                        game_data.is_some_and(|data| data.position.picks_symbol())
+                    }),
+            )
+            .add_systems(
+                Update,
+                takeback.run_if(in_state(AppState::InGame)).run_if(
+                    |game_data: Option<Res<GameData>>| {
+                        game_data.is_some_and(|data| data.mode != Mode::Spectate)
+                    },
+                ),
+            )
+            .add_systems(
+                Update,
+                update_turn_indicator
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_exists::<GameData>),
+            )
+            .add_event::<Pressed>()
+            .add_event::<KIMove>()
+            .add_event::<PlaySound>()
+            .add_systems(
+                Update,
+                (
+                    handle_ki_move,
+                    poll_ki_move.run_if(resource_exists::<KiTask>),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                detect_win_possibilities
+                    .run_if(not(resource_exists::<WinPossibilities>))
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), (despawn_screen::<OnGameScreen>))
+            .add_systems(OnExit(AppState::InGame), |mut commands: Commands| {
+                commands.remove_resource::<KiTask>()
+            })
+            .init_state::<AppState>();
+    }
+}
+
 fn setup(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
@@ -260,30 +638,82 @@ fn setup_game(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     game_data: Res<GameData>,
+    theme: Res<Theme>,
 ) {
     let mut observer = Observer::new(tile_pressed);
-    let cols = 3;
-    let rows = 3;
-    let offset = Vec2::new(
-        rows as f32 * 64.0 - 64.0, // Total width / 2
-        cols as f32 * 64.0 - 64.0, // Total height / 2
+    let tiles = spawn_tiles(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &theme,
+        &mut observer,
+        OnGameScreen,
+        game_data.grid(),
     );
-    for y in 0..cols {
-        for x in 0..rows {
+    commands.spawn(observer);
+    // Games started from the board editor don't begin on an empty board
+    for tile in &tiles {
+        if let Some(symbol) = game_data.symbol_at(tile.index) {
+            spawn_symbol(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &theme,
+                tile,
+                symbol,
+                OnGameScreen,
+            );
+        }
+    }
+    if game_data.position.picks_symbol() && game_data.mode != Mode::Spectate {
+        spawn_symbol_picker(&mut commands, &game_data, theme.palette());
+    }
+    spawn_turn_indicator(&mut commands, &game_data, theme.palette());
+    if game_data.mode != Mode::Spectate {
+        spawn_takeback_buttons(&mut commands, theme.palette());
+    }
+    if game_data.is_computer_turn() {
+        commands.add(move |world: &mut World| {
+            world.send_event(KIMove);
+        });
+    }
+}
+
+/// Spawns a tile for every field of `grid`, watched by `observer`. The layout
+/// they follow becomes the [`BoardLayout`].
+fn spawn_tiles(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    theme: &Theme,
+    observer: &mut Observer<Pressed, ()>,
+    marker: impl Component + Clone,
+    grid: &Grid,
+) -> Vec<Tile> {
+    let (rows, cols) = grid.size();
+    let layout = BoardLayout::new(rows, cols);
+    let step = layout.step;
+    let mut tiles = Vec::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            let index = (row * cols + col) as u32;
+            let pos = layout.tile_center(index);
+            let tile = Tile {
+                pos,
+                size: layout.tile_size(),
+                index,
+            };
             commands.spawn((
                 MaterialMesh2dBundle {
                     mesh: meshes.add(Rectangle::default()).into(),
                     transform: Transform::default()
-                        .with_scale(Vec3::splat(128.))
-                        .with_translation(Vec3::new(
-                            x as f32 * 128.0 - offset.x,
-                            y as f32 * 128.0 - offset.y,
-                            0.0,
-                        )),
-                    material: materials.add(Color::from(WHITE)),
+                        .with_scale(Vec3::splat(step))
+                        .with_translation(pos.extend(0.0)),
+                    material: materials.add(theme.palette().grid),
                     ..default()
                 },
-                OnGameScreen,
+                ThemeColor::Grid,
+                marker.clone(),
             ));
             observer.watch_entity(
                 commands
@@ -291,86 +721,255 @@ fn setup_game(
                         MaterialMesh2dBundle {
                             mesh: meshes.add(Rectangle::default()).into(),
                             transform: Transform::default()
-                                .with_scale(Vec3::splat(120.))
-                                .with_translation(Vec3::new(
-                                    x as f32 * 128.0 - offset.x,
-                                    y as f32 * 128.0 - offset.y,
-                                    0.1,
-                                )),
-                            material: materials.add(Color::srgb_u8(43, 44, 47)),
+                                .with_scale(Vec3::splat(tile.size))
+                                .with_translation(pos.extend(0.1)),
+                            material: materials.add(theme.tile(false)),
                             ..default()
                         },
-                        Tile {
-                            pos: Vec2::new(x as f32 * 128. - offset.x, y as f32 * 128. - offset.y),
-                            size: 120.,
-                            index: x + 6 - (y * 3),
-                        },
-                        OnGameScreen,
+                        tile.clone(),
+                        marker.clone(),
                     ))
                     .id(),
             );
+            tiles.push(tile);
         }
     }
-    commands.spawn(observer);
-    if matches!(game_data.player, PlayerChoice::O) {
-        commands.add(move |world: &mut World| {
-            world.send_event(KIMove);
-        });
+    commands.insert_resource(layout);
+    tiles
+}
+
+fn turn_text(game_data: &GameData, thinking: bool) -> String {
+    if game_data.won {
+        "Game over".to_string()
+    } else if thinking {
+        format!("{:?} is thinking…", game_data.who_moves())
+    } else {
+        format!("{:?} to move", game_data.who_moves())
     }
 }
 
-fn handle_click(
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    camera: Query<(&Camera, &GlobalTransform)>,
-    windows: Query<&Window>,
+fn spawn_turn_indicator(commands: &mut Commands, game_data: &GameData, palette: &Palette) {
+    commands.spawn((
+        TextBundle::from_section(turn_text(game_data, false), palette.text_style()).with_style(
+            Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..Default::default()
+            },
+        ),
+        TurnText,
+        OnGameScreen,
+    ));
+}
+
+fn update_turn_indicator(
+    game_data: Res<GameData>,
+    ki_task: Option<Res<KiTask>>,
+    mut text_query: Query<&mut Text, With<TurnText>>,
+) {
+    let value = turn_text(&game_data, ki_task.is_some());
+    for mut text in &mut text_query {
+        // Only touch the text when it differs, so the UI isn't laid out anew
+        // every frame
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+fn spawn_takeback_buttons(commands: &mut Commands, palette: &Palette) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    column_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for (label, action) in [
+                ("Undo", TakebackAction::Undo),
+                ("Redo", TakebackAction::Redo),
+            ] {
+                parent
+                    .spawn((palette.button_bundle(), action))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, palette.text_style()));
+                    });
+            }
+        });
+}
+
+/// Takes moves back and replays them, through the buttons or Ctrl+Z / Ctrl+Y
+#[allow(clippy::too_many_arguments)]
+fn takeback(
+    interaction_query: ButtonInteractions<TakebackAction>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game_data: ResMut<GameData>,
+    tiles: Query<&Tile>,
+    symbols: Query<(Entity, &Symbol)>,
+    play_again: Query<Entity, With<OnPlayAgainScreen>>,
+    theme: Res<Theme>,
     mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let (camera, camera_transform) = camera.single();
-    if let Some(pos) = windows
-        .single()
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-        .map(|ray| ray.origin.truncate())
-    {
-        if mouse_button_input.just_pressed(MouseButton::Left) {
-            commands.trigger(Click(pos, 1.0));
+    let mut action = interaction_query.iter().find_map(|(interaction, action)| {
+        (*interaction == Interaction::Pressed).then_some(*action)
+    });
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        if keys.just_pressed(KeyCode::KeyZ) {
+            action = Some(TakebackAction::Undo);
+        }
+        if keys.just_pressed(KeyCode::KeyY) {
+            action = Some(TakebackAction::Redo);
         }
     }
+    match action {
+        Some(TakebackAction::Undo) => {
+            let undone = game_data.undo();
+            if undone.is_empty() {
+                return;
+            }
+            // The computer may be thinking about a position that is gone now
+            commands.remove_resource::<KiTask>();
+            commands.remove_resource::<WinPossibilities>();
+            for entity in &play_again {
+                commands.entity(entity).despawn_recursive();
+            }
+            for (entity, symbol) in &symbols {
+                if undone.contains(&symbol.index) {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+        Some(TakebackAction::Redo) => {
+            let redone = game_data.redo();
+            for tile in tiles.iter().filter(|tile| redone.contains(&tile.index)) {
+                if let Some(symbol) = game_data.symbol_at(tile.index) {
+                    spawn_symbol(
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                        &theme,
+                        tile,
+                        symbol,
+                        OnGameScreen,
+                    );
+                }
+            }
+            if !redone.is_empty() && !game_data.won && game_data.is_computer_turn() {
+                commands.add(move |world: &mut World| {
+                    world.send_event(KIMove);
+                });
+            }
+        }
+        None => {}
+    }
 }
 
-fn on_add_tile(
-    trigger: Trigger<OnAdd, Tile>,
-    query: Query<&Tile>,
-    mut index: ResMut<SpatialIndex>,
+fn spawn_symbol_picker(commands: &mut Commands, game_data: &GameData, palette: &Palette) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(20.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("Placing: {:?}", game_data.symbol),
+                    palette.text_style(),
+                ),
+                SymbolPickerText,
+            ));
+            parent
+                .spawn((palette.button_bundle(), SymbolPicker))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Switch (X/O)",
+                        palette.text_style(),
+                    ));
+                });
+        });
+}
+
+/// Lets the human choose which symbol to place in wild games, either through
+/// the switch button or by pressing X / O.
+fn pick_symbol(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SymbolPicker>)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game_data: ResMut<GameData>,
+    mut text_query: Query<&mut Text, With<SymbolPickerText>>,
 ) {
-    let mine = query.get(trigger.entity()).unwrap();
-    let tile = (
-        (mine.pos.x / CELL_SIZE).floor() as i32,
-        (mine.pos.y / CELL_SIZE).floor() as i32,
-    );
-    index.map.entry(tile).or_default().insert(trigger.entity());
+    let mut symbol = game_data.symbol;
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        symbol = symbol.opposite();
+    }
+    if keys.just_pressed(KeyCode::KeyX) {
+        symbol = PlayerChoice::X;
+    }
+    if keys.just_pressed(KeyCode::KeyO) {
+        symbol = PlayerChoice::O;
+    }
+    if symbol != game_data.symbol {
+        for mut text in &mut text_query {
+            text.sections[0].value = format!("Placing: {:?}", symbol);
+        }
+        game_data.symbol = symbol;
+    }
 }
 
-// Remove despawned mines from our index
-fn on_remove_tile(
-    trigger: Trigger<OnRemove, Tile>,
-    query: Query<&Tile>,
-    mut index: ResMut<SpatialIndex>,
+/// Presses the tile under the mouse
+fn handle_click(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    windows: Query<&Window>,
+    layout: Option<Res<BoardLayout>>,
+    tiles: Query<(Entity, &Tile)>,
+    mut commands: Commands,
 ) {
-    let mine = query.get(trigger.entity()).unwrap();
-    let tile = (
-        (mine.pos.x / CELL_SIZE).floor() as i32,
-        (mine.pos.y / CELL_SIZE).floor() as i32,
-    );
-    index.map.entry(tile).and_modify(|set| {
-        set.remove(&trigger.entity());
-    });
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (camera, camera_transform) = camera.single();
+    let Some(index) = cursor_world_position(windows.single(), camera, camera_transform)
+        .zip(layout)
+        .and_then(|(pos, layout)| layout.tile_at(pos))
+    else {
+        return;
+    };
+    if let Some((entity, _)) = tiles.iter().find(|(_, tile)| tile.index == index) {
+        commands.trigger_targets(Pressed, entity);
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn tile_pressed(
     trigger: Trigger<Pressed>,
     query: Query<&Tile>,
     mut game_data: ResMut<GameData>,
+    mut sounds: EventWriter<PlaySound>,
+    theme: Res<Theme>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
@@ -382,58 +981,43 @@ fn tile_pressed(
 
     let tile = query.get(id).unwrap();
     if let Ok(result) = game_data.make_move(tile.index) {
+        sounds.send(PlaySound(Sound::Place));
+        let Some(symbol) = game_data.symbol_at(tile.index) else {
+            return;
+        };
         match result {
             MoveResult::Moved(_) => {
                 spawn_symbol(
                     &mut commands,
                     &mut meshes,
                     &mut materials,
-                    tile.pos,
-                    game_data.player.clone(),
+                    &theme,
+                    tile,
+                    symbol,
+                    OnGameScreen,
                 );
                 // Trigger KI move if it's not game over
-                commands.add(move |world: &mut World| {
-                    world.send_event(KIMove);
-                });
+                if game_data.is_computer_turn() {
+                    commands.add(move |world: &mut World| {
+                        world.send_event(KIMove);
+                    });
+                }
             }
             MoveResult::Won(_) => {
                 spawn_symbol(
                     &mut commands,
                     &mut meshes,
                     &mut materials,
-                    tile.pos,
-                    game_data.player.clone(),
+                    &theme,
+                    tile,
+                    symbol,
+                    OnGameScreen,
                 );
                 // Handle win condition
             }
         }
-    }
-}
-
-#[derive(Resource, Default)]
-struct SpatialIndex {
-    map: HashMap<(i32, i32), HashSet<Entity>>,
-}
-
-/// Cell size has to be bigger than any `TriggerMine::radius`
-const CELL_SIZE: f32 = 48.0;
-
-impl SpatialIndex {
-    // Lookup all entities within adjacent cells of our spatial index
-    fn get_nearby(&self, pos: Vec2) -> Vec<Entity> {
-        let tile = (
-            (pos.x / CELL_SIZE).floor() as i32,
-            (pos.y / CELL_SIZE).floor() as i32,
-        );
-        let mut nearby = Vec::new();
-        for x in -1..2 {
-            for y in -1..2 {
-                if let Some(entities) = self.map.get(&(tile.0 + x, tile.1 + y)) {
-                    nearby.extend(entities.iter());
-                }
-            }
-        }
-        nearby
+    } else {
+        sounds.send(PlaySound(Sound::Invalid));
     }
 }
 
@@ -443,91 +1027,141 @@ pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut comm
     }
 }
 
+/// Starts a search for the computer's move, unless one is already running
 fn handle_ki_move(
+    game_data: Res<GameData>,
+    ki_task: Option<Res<KiTask>>,
+    mut ki_move_events: EventReader<KIMove>,
+    mut commands: Commands,
+) {
+    if ki_move_events.read().count() == 0 || ki_task.is_some() || !game_data.is_computer_turn() {
+        return;
+    }
+    let search = game_data.ki_search();
+    commands.insert_resource(KiTask {
+        task: AsyncComputeTaskPool::get().spawn(async move { search.run() }),
+        moves: game_data.moves,
+    });
+}
+
+/// Plays the computer's move once its search has finished
+#[allow(clippy::too_many_arguments)]
+fn poll_ki_move(
+    mut ki_task: ResMut<KiTask>,
     mut game_data: ResMut<GameData>,
     query: Query<&Tile>,
+    mut sounds: EventWriter<PlaySound>,
+    theme: Res<Theme>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut ki_move_events: EventReader<KIMove>,
 ) {
-    for _ in ki_move_events.read() {
-        if let Ok(result) = game_data.make_ki_move() {
-            match result {
-                MoveResult::Moved(index) => {
-                    if let Some(tile) = query.iter().find(|t| t.index == index) {
-                        spawn_symbol(
-                            &mut commands,
-                            &mut meshes,
-                            &mut materials,
-                            tile.pos,
-                            game_data.player.opposite(),
-                        );
-                    }
-                }
-                MoveResult::Won(index) => {
-                    if let Some(tile) = query.iter().find(|t| t.index == index) {
-                        spawn_symbol(
-                            &mut commands,
-                            &mut meshes,
-                            &mut materials,
-                            tile.pos,
-                            game_data.player.opposite(),
-                        );
-                    }
-                }
-            }
-        }
+    let Some(best_move) = block_on(future::poll_once(&mut ki_task.task)) else {
+        return;
+    };
+    commands.remove_resource::<KiTask>();
+    if ki_task.moves != game_data.moves {
+        return;
+    }
+    let Some(result) = best_move.and_then(|best_move| game_data.make_ki_move(best_move).ok())
+    else {
+        return;
+    };
+    let (MoveResult::Moved(index) | MoveResult::Won(index)) = result;
+    sounds.send(PlaySound(Sound::Place));
+    if let (Some(tile), Some(symbol)) = (
+        query.iter().find(|t| t.index == index),
+        game_data.symbol_at(index),
+    ) {
+        spawn_symbol(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &theme,
+            tile,
+            symbol,
+            OnGameScreen,
+        );
     }
 }
 
 fn spawn_symbol(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    position: Vec2,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    theme: &Theme,
+    tile: &Tile,
     player_choice: PlayerChoice,
+    marker: impl Bundle + Clone,
 ) {
+    // Symbols keep the proportions they have on the classic 120px tiles
+    let symbol_size = tile.size * 100.0 / 120.0;
+    let color = theme.symbol(player_choice);
     match player_choice {
-        PlayerChoice::X => spawn_x(commands, meshes, materials, position),
-        PlayerChoice::O => spawn_o(commands, meshes, materials, position),
+        PlayerChoice::X => spawn_x(
+            commands,
+            meshes,
+            materials,
+            tile,
+            symbol_size,
+            color,
+            marker,
+        ),
+        PlayerChoice::O => spawn_o(
+            commands,
+            meshes,
+            materials,
+            tile,
+            symbol_size,
+            color,
+            marker,
+        ),
     }
 }
 
 fn spawn_x(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    position: Vec2,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    tile: &Tile,
+    symbol_size: f32,
+    color: Color,
+    marker: impl Bundle + Clone,
 ) {
-    let symbol_size = 100.0;
-
     // Spawn first line of X (diagonal from top-left to bottom-right)
     commands.spawn((
         MaterialMesh2dBundle {
             mesh: meshes.add(Rectangle::default()).into(),
-            transform: Transform::from_xyz(position.x, position.y, 0.2)
+            transform: Transform::from_xyz(tile.pos.x, tile.pos.y, 0.2)
                 .with_rotation(Quat::from_rotation_z(45f32.to_radians()))
                 .with_scale(Vec3::new(symbol_size, symbol_size / 8.0, 1.0)),
-            material: materials.add(Color::from(GRAY_50)),
+            material: materials.add(color),
             ..default()
         },
-        Symbol { is_x: true },
-        OnGameScreen,
+        Symbol {
+            is_x: true,
+            index: tile.index,
+        },
+        Drawing::line(tile.pos, symbol_size, 0),
+        marker.clone(),
     ));
 
     // Spawn second line of X (diagonal from top-right to bottom-left)
     commands.spawn((
         MaterialMesh2dBundle {
             mesh: meshes.add(Rectangle::default()).into(),
-            transform: Transform::from_xyz(position.x, position.y, 0.2)
+            transform: Transform::from_xyz(tile.pos.x, tile.pos.y, 0.2)
                 .with_rotation(Quat::from_rotation_z(-45f32.to_radians()))
                 .with_scale(Vec3::new(symbol_size, symbol_size / 8.0, 1.0)),
-            material: materials.add(Color::from(GRAY_50)),
+            material: materials.add(color),
             ..default()
         },
-        Symbol { is_x: true },
-        OnGameScreen,
+        Symbol {
+            is_x: true,
+            index: tile.index,
+        },
+        Drawing::line(tile.pos, symbol_size, 1),
+        marker,
     ));
 }
 
@@ -540,26 +1174,52 @@ fn detect_win_possibilities(mut game_data: ResMut<GameData>, mut commands: Comma
 
 fn spawn_o(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    position: Vec2,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    tile: &Tile,
+    symbol_size: f32,
+    color: Color,
+    marker: impl Bundle + Clone,
 ) {
-    let symbol_size = 100.0;
-
     commands.spawn((
         MaterialMesh2dBundle {
             mesh: meshes.add(Circle::new(symbol_size / 2.0)).into(),
-            transform: Transform::from_xyz(position.x, position.y, 0.2)
+            transform: Transform::from_xyz(tile.pos.x, tile.pos.y, 0.2)
                 .with_scale(Vec3::splat(1.0)),
-            material: materials.add(Color::from(GRAY_50)),
+            material: materials.add(color),
             ..default()
         },
-        Symbol { is_x: false },
-        OnGameScreen,
+        Symbol {
+            is_x: false,
+            index: tile.index,
+        },
+        Drawing::arc(symbol_size / 2.0),
+        marker,
     ));
 }
 
-fn spawn_play_again(mut commands: Commands, win: Res<WinPossibilities>) {
+fn spawn_play_again(
+    mut commands: Commands,
+    win: Res<WinPossibilities>,
+    game_data: Res<GameData>,
+    score: Option<Res<MatchScore>>,
+    theme: Res<Theme>,
+) {
+    let palette = theme.palette();
+    // Spectated matches go on with a rematch instead
+    if game_data.mode == Mode::Spectate {
+        return;
+    }
+    let (summary, label, action) = match score.map(|score| score.with_result(Some(&win))) {
+        Some(score) if score.is_decided() => (
+            format!("  {}", score.summary(game_data.mode)),
+            "Play Again",
+            ButtonAction::PlayAgain,
+        ),
+        Some(_) => (String::new(), "Next Game", ButtonAction::NextGame),
+        None => (String::new(), "Play Again", ButtonAction::PlayAgain),
+    };
+    let text = format!("{}{}  Hints used: {}", *win, summary, game_data.hints);
     commands
         .spawn((
             NodeBundle {
@@ -574,16 +1234,11 @@ fn spawn_play_again(mut commands: Commands, win: Res<WinPossibilities>) {
             },
             OnGameScreen,
             OnPlayAgainScreen,
+            FadeIn::result(&win),
         ))
         .with_children(|parent| {
             parent.spawn(
-                TextBundle::from_section(
-                    win.to_string(),
-                    TextStyle {
-                        ..Default::default()
-                    },
-                )
-                .with_style(Style {
+                TextBundle::from_section(text, palette.text_style()).with_style(Style {
                     align_self: AlignSelf::Center,
                     ..Default::default()
                 }),
@@ -591,54 +1246,36 @@ fn spawn_play_again(mut commands: Commands, win: Res<WinPossibilities>) {
             parent
                 .spawn((
                     ButtonBundle {
-                        background_color: BackgroundColor(BLUE_400.into()),
-                        border_radius: BorderRadius::all(Val::Percent(20.0)),
-                        border_color: BorderColor(BLACK.into()),
                         style: Style {
-                            border: UiRect::all(Val::Px(1.0)),
-                            padding: UiRect::all(Val::Px(20.0))
-                                .with_top(Val::Px(5.0))
-                                .with_bottom(Val::Px(5.0)),
                             margin: UiRect::all(Val::Px(20.0)),
                             align_self: AlignSelf::Center,
                             justify_self: JustifySelf::End,
-                            ..Default::default()
+                            ..Palette::button_style()
                         },
-                        ..Default::default()
+                        ..palette.button_bundle()
                     },
-                    ButtonAction::PlayAgain,
+                    action,
                 ))
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Play Again",
-                        TextStyle {
-                            ..Default::default()
-                        },
-                    ));
+                    parent.spawn(TextBundle::from_section(label, palette.text_style()));
                 });
         });
 }
 
 fn button_hover_system(
-    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>, Without<ThemeColor>),
+    >,
+    theme: Res<Theme>,
 ) {
     for (interaction, mut color) in &mut buttons {
-        match *interaction {
-            Interaction::Pressed => {
-                *color = BackgroundColor(BLUE_900.into());
-            }
-            Interaction::Hovered => {
-                *color = BackgroundColor(BLUE_800.into());
-            }
-            Interaction::None => {
-                *color = BackgroundColor(BLUE_400.into());
-            }
-        }
+        *color = BackgroundColor(theme.button(*interaction));
     }
 }
 
 fn menu_action(
-    interaction_query: Query<(&Interaction, &ButtonAction), (Changed<Interaction>, With<Button>)>,
+    interaction_query: ButtonInteractions<ButtonAction>,
     mut app_state: ResMut<NextState<AppState>>,
     mut commands: Commands,
 ) {
@@ -649,9 +1286,210 @@ fn menu_action(
                     info!("Playing Again");
                     commands.remove_resource::<GameData>();
                     commands.remove_resource::<WinPossibilities>();
+                    commands.remove_resource::<MatchScore>();
                     app_state.set(AppState::InMenu);
                 }
+                // The scoreboard sets up the board for the next game
+                ButtonAction::NextGame => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::AssetPlugin, input::InputPlugin, state::app::StatesPlugin, window::WindowResized,
+    };
+
+    use super::*;
+
+    /// Headless app on the game screen, with the human playing `player`
+    /// against the engine
+    fn game_app(player: PlayerChoice) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            InputPlugin,
+            StatesPlugin,
+            GamePlugin,
+            AnimationPlugin,
+            ThemePlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .add_event::<WindowResized>()
+        .insert_resource(GameData::new(
+            Grid::new(3, 3),
+            player,
+            Variant::Classic,
+            Mode::Computer,
+            Default::default(),
+        ));
+        app.world_mut().spawn(Window::default());
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+        app
+    }
+
+    /// Plays the human's side by clicking the first empty tile whenever it is
+    /// their turn, until the game is decided
+    fn play_to_completion(app: &mut App) -> WinPossibilities {
+        for _ in 0..10_000 {
+            if let Some(win) = app.world_mut().remove_resource::<WinPossibilities>() {
+                return win;
+            }
+            let game_data = app.world().resource::<GameData>();
+            if !game_data.is_computer_turn() {
+                let index = (0..9)
+                    .find(|&index| game_data.symbol_at(index).is_none())
+                    .unwrap();
+                let tile = app
+                    .world_mut()
+                    .query::<(Entity, &Tile)>()
+                    .iter(app.world())
+                    .find(|(_, tile)| tile.index == index)
+                    .map(|(entity, _)| entity)
+                    .unwrap();
+                app.world_mut().trigger_targets(Pressed, tile);
+            } else {
+                // Give the search on the task pool time to finish
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            app.update();
+        }
+        panic!("game did not finish");
+    }
+
+    /// Number of symbols on the board, an X is drawn as two bars
+    fn symbols(app: &mut App) -> usize {
+        app.world_mut()
+            .query::<&Symbol>()
+            .iter(app.world())
+            .map(|symbol| if symbol.is_x { 1 } else { 2 })
+            .sum::<usize>()
+            / 2
+    }
+
+    #[test]
+    fn human_as_x_plays_to_completion() {
+        let mut app = game_app(PlayerChoice::X);
+        let win = play_to_completion(&mut app);
+        assert_eq!(win, WinPossibilities::OWon);
+
+        let game_data = app.world().resource::<GameData>();
+        assert!(game_data.won);
+        assert_eq!(game_data.who_moves(), PlayerChoice::X);
+        let moves = game_data.moves as usize;
+        assert_eq!(symbols(&mut app), moves);
+    }
+
+    #[test]
+    fn human_as_o_plays_to_completion() {
+        let mut app = game_app(PlayerChoice::O);
+        let win = play_to_completion(&mut app);
+        assert_eq!(win, WinPossibilities::XWon);
+
+        let game_data = app.world().resource::<GameData>();
+        assert!(game_data.won);
+        assert_eq!(game_data.who_moves(), PlayerChoice::O);
+        let moves = game_data.moves as usize;
+        assert_eq!(symbols(&mut app), moves);
+    }
+
+    #[test]
+    fn computer_win_as_x_is_reported() {
+        let mut game_data = GameData::new(
+            "1 1 0\n2 2 0\n0 0 0".parse().unwrap(),
+            PlayerChoice::O,
+            Variant::Classic,
+            Mode::Computer,
+            Default::default(),
+        );
+        // The human can't play the computer's moves
+        assert!(game_data.make_move(5).is_err());
+        let best_move = game_data.ki_search().run().unwrap();
+        assert!(matches!(
+            game_data.make_ki_move(best_move),
+            Ok(MoveResult::Won(2))
+        ));
+        assert!(game_data.won);
+    }
+
+    #[test]
+    fn undo_takes_back_the_human_move_and_the_reply() {
+        let mut app = game_app(PlayerChoice::X);
+        let tile = app
+            .world_mut()
+            .query::<(Entity, &Tile)>()
+            .iter(app.world())
+            .find(|(_, tile)| tile.index == 0)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.world_mut().trigger_targets(Pressed, tile);
+        for _ in 0..10_000 {
+            if app.world().resource::<GameData>().moves == 2 {
+                break;
             }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            app.update();
         }
+        let reply = app.world().resource::<GameData>().history[1];
+        assert_eq!(symbols(&mut app), 2);
+
+        let mut buttons = app
+            .world_mut()
+            .query::<(&mut Interaction, &TakebackAction)>();
+        for (mut interaction, action) in buttons.iter_mut(app.world_mut()) {
+            if matches!(action, TakebackAction::Undo) {
+                *interaction = Interaction::Pressed;
+            }
+        }
+        app.update();
+        let game_data = app.world().resource::<GameData>();
+        assert_eq!(game_data.moves, 0);
+        assert!((0..9).all(|index| game_data.symbol_at(index).is_none()));
+        assert_eq!(symbols(&mut app), 0);
+
+        let mut game_data = app.world_mut().resource_mut::<GameData>();
+        assert_eq!(game_data.redo(), vec![0, reply.index as u32]);
+        assert_eq!(game_data.moves, 2);
+        assert!(game_data.undone.is_empty());
+    }
+
+    #[test]
+    fn undo_keeps_the_computers_opening_move() {
+        let mut game_data = GameData::new(
+            Grid::new(3, 3),
+            PlayerChoice::O,
+            Variant::Classic,
+            Mode::Computer,
+            Default::default(),
+        );
+        let opening = game_data.ki_search().run().unwrap();
+        game_data.make_ki_move(opening).unwrap();
+        assert!(game_data.undo().is_empty());
+        assert_eq!(game_data.moves, 1);
+        assert_eq!(game_data.history, vec![opening]);
+    }
+
+    #[test]
+    fn winning_tiles_are_those_of_the_completed_lines() {
+        let mut game_data = GameData::new(
+            "1 1 1\n2 1 0\n2 0 1".parse().unwrap(),
+            PlayerChoice::X,
+            Variant::Classic,
+            Mode::Hotseat,
+            Default::default(),
+        );
+        assert_eq!(game_data.check_game_state(), WinPossibilities::XWon);
+        assert_eq!(game_data.winning_tiles(), vec![0, 1, 2, 4, 8]);
+
+        game_data.position =
+            Variant::Classic.position("1 2 1\n2 1 0\n2 0 0".parse().unwrap(), PlayerChoice::O);
+        assert!(game_data.winning_tiles().is_empty());
     }
 }
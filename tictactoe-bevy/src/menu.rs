@@ -1,13 +1,14 @@
-use bevy::{
-    color::palettes::{
-        css::{BLACK, RED},
-        tailwind::{BLUE_400, BLUE_50, BLUE_700, BLUE_800, BLUE_900},
-    },
-    prelude::*,
-};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use tictactoe_logic::grid::Grid;
 
-use crate::{despawn_screen, AppState, GameData, PlayerChoice};
+use crate::{
+    despawn_screen,
+    save::Slot,
+    scoreboard::{MatchScore, MATCH_LENGTHS},
+    theme::{Palette, Theme, ThemeColor},
+    AppState, ButtonInteractions, EngineSettings, GameData, Mode, PlayerChoice, Variant, MAX_DEPTH,
+};
 pub struct MenuPlugin;
 
 #[derive(States, Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -20,23 +21,186 @@ enum MenuState {
 #[derive(Component)]
 struct OnMenuScreen;
 
+/// Options picked in the menu that are used to set up the next game
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Side the human played last
+    pub side: PlayerChoice,
+    pub variant: Variant,
+    pub mode: Mode,
+    pub rows: usize,
+    pub cols: usize,
+    pub win_length: usize,
+    /// Games of a match, 1 for a single game
+    pub best_of: u32,
+    /// Computer players for X and O
+    pub engines: [EngineSettings; 2],
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            side: PlayerChoice::default(),
+            variant: Variant::default(),
+            mode: Mode::default(),
+            rows: 3,
+            cols: 3,
+            win_length: 3,
+            best_of: 1,
+            engines: Default::default(),
+        }
+    }
+}
+
+/// Smallest and largest number of rows or columns the menu offers
+pub const MIN_SIZE: usize = 3;
+pub const MAX_SIZE: usize = 7;
+
+impl GameSettings {
+    /// Empty board for the next game
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.rows, self.cols).with_win_length(self.win_length)
+    }
+
+    fn label(&self, text: SettingText) -> String {
+        match text {
+            SettingText::Variant => format!("Variant: {}", self.variant),
+            SettingText::Mode => format!("Mode: {}", self.mode),
+            SettingText::Match if self.best_of > 1 => format!("Match: Best of {}", self.best_of),
+            SettingText::Match => "Match: Single game".to_string(),
+            SettingText::Engine(side) => {
+                format!("{:?} engine: {}", side, self.engines[side as usize].engine)
+            }
+            SettingText::Stepper(setting) => format!("{}: {}", setting, self.value(setting)),
+        }
+    }
+
+    fn value(&self, setting: Setting) -> usize {
+        match setting {
+            Setting::Rows => self.rows,
+            Setting::Cols => self.cols,
+            Setting::WinLength => self.win_length,
+            Setting::Depth(side) => self.engines[side as usize].depth as usize,
+        }
+    }
+
+    /// Changes a setting by `step`, keeping the win length between 3 and the
+    /// shorter side of the board and the search depth between 1 and
+    /// [`MAX_DEPTH`]
+    fn adjust(&mut self, setting: Setting, step: isize) {
+        let clamp =
+            |value: usize, max: usize| value.saturating_add_signed(step).clamp(MIN_SIZE, max);
+        match setting {
+            Setting::Rows => self.rows = clamp(self.rows, MAX_SIZE),
+            Setting::Cols => self.cols = clamp(self.cols, MAX_SIZE),
+            Setting::WinLength => {
+                self.win_length = clamp(self.win_length, self.rows.min(self.cols))
+            }
+            Setting::Depth(side) => {
+                let depth = &mut self.engines[side as usize].depth;
+                *depth = (*depth + step as i8).clamp(1, MAX_DEPTH);
+            }
+        }
+        self.win_length = self.win_length.min(self.rows.min(self.cols));
+    }
+
+    /// Brings values edited by hand back into the ranges the menu offers
+    pub fn clamp(&mut self) {
+        for setting in [
+            Setting::Rows,
+            Setting::Cols,
+            Setting::WinLength,
+            Setting::Depth(PlayerChoice::X),
+            Setting::Depth(PlayerChoice::O),
+        ] {
+            self.adjust(setting, 0);
+        }
+        if !MATCH_LENGTHS.contains(&self.best_of) {
+            self.best_of = 1;
+        }
+    }
+
+    /// Next of the [`MATCH_LENGTHS`]
+    fn next_best_of(&self) -> u32 {
+        let index = MATCH_LENGTHS
+            .iter()
+            .position(|&length| length == self.best_of)
+            .map_or(0, |index| index + 1);
+        MATCH_LENGTHS[index % MATCH_LENGTHS.len()]
+    }
+
+    /// Match to keep score of when a game is started as `side`, if any
+    fn match_score(&self, side: PlayerChoice) -> Option<MatchScore> {
+        (self.best_of > 1 && self.mode != Mode::Spectate)
+            .then(|| MatchScore::new(self.best_of, side))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Rows,
+    Cols,
+    WinLength,
+    /// How many moves ahead the engine of one side looks
+    Depth(PlayerChoice),
+}
+
+impl std::fmt::Display for Setting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Setting::Rows => write!(f, "Rows"),
+            Setting::Cols => write!(f, "Columns"),
+            Setting::WinLength => write!(f, "Win length"),
+            Setting::Depth(side) => write!(f, "{:?} depth", side),
+        }
+    }
+}
+
+/// Shows the current value of one of the [`GameSettings`]
+#[derive(Component, Clone, Copy)]
+enum SettingText {
+    Variant,
+    Mode,
+    Match,
+    Engine(PlayerChoice),
+    Stepper(Setting),
+}
+
 #[derive(Component)]
 enum MenuButtonAction {
     PlayX,
     PlayO,
+    ToggleVariant,
+    ToggleMode,
+    ToggleMatch,
+    ToggleEngine(PlayerChoice),
+    Adjust(Setting, isize),
+    /// Resumes the game saved in the slot
+    Load(Slot),
+    Editor,
+    Settings,
+    Statistics,
     Exit,
 }
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<MenuState>()
+            .init_resource::<GameSettings>()
             .add_systems(OnEnter(AppState::InMenu), setup_menu)
             .add_systems(Update, (menu_action).run_if(in_state(MenuState::InMenu)))
             .add_systems(OnExit(AppState::InMenu), despawn_screen::<OnMenuScreen>);
     }
 }
 
-fn setup_menu(mut menu_state: ResMut<NextState<MenuState>>, mut commands: Commands) {
+fn setup_menu(
+    mut menu_state: ResMut<NextState<MenuState>>,
+    settings: Res<GameSettings>,
+    theme: Res<Theme>,
+    mut commands: Commands,
+) {
+    let palette = theme.palette();
     menu_state.set(MenuState::InMenu);
     commands
         .spawn((
@@ -54,22 +218,25 @@ fn setup_menu(mut menu_state: ResMut<NextState<MenuState>>, mut commands: Comman
         ))
         .with_children(|parent| {
             parent
-                .spawn(NodeBundle {
-                    border_radius: BorderRadius::all(Val::Percent(5.0)),
-                    background_color: BackgroundColor(BLUE_700.into()),
-                    border_color: BorderColor(BLUE_800.into()),
-                    style: Style {
-                        border: UiRect::all(Val::Px(1.0)),
-                        padding: UiRect::all(Val::Px(5.0)),
-                        min_width: Val::Vw(20.0),
-                        min_height: Val::Vh(20.0),
-                        flex_direction: FlexDirection::Column,
-                        justify_content: JustifyContent::SpaceBetween,
-                        align_items: AlignItems::Center,
+                .spawn((
+                    NodeBundle {
+                        border_radius: BorderRadius::all(Val::Percent(5.0)),
+                        background_color: BackgroundColor(palette.panel),
+                        border_color: BorderColor(palette.panel_border),
+                        style: Style {
+                            border: UiRect::all(Val::Px(1.0)),
+                            padding: UiRect::all(Val::Px(5.0)),
+                            min_width: Val::Vw(20.0),
+                            min_height: Val::Vh(20.0),
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
                         ..Default::default()
                     },
-                    ..Default::default()
-                })
+                    ThemeColor::Panel,
+                ))
                 .with_children(|parent| {
                     parent
                         .spawn(NodeBundle {
@@ -84,97 +251,324 @@ fn setup_menu(mut menu_state: ResMut<NextState<MenuState>>, mut commands: Comman
                             parent
                                 .spawn((
                                     ButtonBundle {
-                                        background_color: BackgroundColor(BLUE_400.into()),
-                                        border_radius: BorderRadius::all(Val::Percent(20.0)),
-                                        border_color: BorderColor(BLACK.into()),
                                         style: Style {
-                                            border: UiRect::all(Val::Px(1.0)),
-                                            padding: UiRect::all(Val::Px(20.0))
-                                                .with_top(Val::Px(5.0))
-                                                .with_bottom(Val::Px(5.0)),
                                             justify_self: JustifySelf::Start,
                                             align_self: AlignSelf::Start,
-                                            ..Default::default()
+                                            ..Palette::button_style()
                                         },
-                                        ..Default::default()
+                                        ..palette.button_bundle()
                                     },
                                     MenuButtonAction::PlayX,
                                 ))
                                 .with_children(|parent| {
                                     parent.spawn(TextBundle::from_section(
                                         "Play X",
-                                        TextStyle {
-                                            ..Default::default()
-                                        },
+                                        palette.text_style(),
                                     ));
                                 });
                             parent
                                 .spawn((
                                     ButtonBundle {
-                                        background_color: BackgroundColor(BLUE_400.into()),
-                                        border_radius: BorderRadius::all(Val::Percent(20.0)),
-                                        border_color: BorderColor(BLACK.into()),
                                         style: Style {
-                                            border: UiRect::all(Val::Px(1.0)),
-                                            padding: UiRect::all(Val::Px(20.0))
-                                                .with_top(Val::Px(5.0))
-                                                .with_bottom(Val::Px(5.0)),
                                             justify_self: JustifySelf::Start,
                                             align_self: AlignSelf::Start,
-                                            ..Default::default()
+                                            ..Palette::button_style()
                                         },
-                                        ..Default::default()
+                                        ..palette.button_bundle()
                                     },
                                     MenuButtonAction::PlayO,
                                 ))
                                 .with_children(|parent| {
                                     parent.spawn(TextBundle::from_section(
                                         "Play O",
-                                        TextStyle {
-                                            ..Default::default()
-                                        },
+                                        palette.text_style(),
                                     ));
                                 });
                         });
                 })
+                .with_children(|parent| {
+                    let saves: Vec<Slot> = Slot::all().filter(|slot| slot.exists()).collect();
+                    if !saves.is_empty() {
+                        spawn_saves_row(parent, palette, &saves);
+                    }
+                })
                 .with_children(|parent| {
                     parent
                         .spawn((
                             ButtonBundle {
-                                background_color: BackgroundColor(RED.into()),
-                                border_radius: BorderRadius::all(Val::Percent(20.0)),
-                                border_color: BorderColor(BLACK.into()),
                                 style: Style {
-                                    border: UiRect::all(Val::Px(1.0)),
-                                    padding: UiRect::all(Val::Px(20.0))
-                                        .with_top(Val::Px(5.0))
-                                        .with_bottom(Val::Px(5.0)),
-                                    ..Default::default()
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
                                 },
-                                ..Default::default()
+                                ..palette.button_bundle()
                             },
-                            MenuButtonAction::Exit,
+                            MenuButtonAction::ToggleVariant,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    settings.label(SettingText::Variant),
+                                    palette.text_style(),
+                                ),
+                                SettingText::Variant,
+                            ));
+                        });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
+                                },
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::ToggleMode,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    settings.label(SettingText::Mode),
+                                    palette.text_style(),
+                                ),
+                                SettingText::Mode,
+                            ));
+                        });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
+                                },
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::ToggleMatch,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    settings.label(SettingText::Match),
+                                    palette.text_style(),
+                                ),
+                                SettingText::Match,
+                            ));
+                        });
+                })
+                .with_children(|parent| {
+                    for setting in [Setting::Rows, Setting::Cols, Setting::WinLength] {
+                        spawn_stepper(parent, palette, setting, &settings);
+                    }
+                })
+                .with_children(|parent| {
+                    for side in [PlayerChoice::X, PlayerChoice::O] {
+                        spawn_engine_row(parent, palette, side, &settings);
+                    }
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
+                                },
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::Settings,
+                        ))
+                        .with_children(|parent| {
+                            parent
+                                .spawn(TextBundle::from_section("Settings", palette.text_style()));
+                        });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
+                                },
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::Statistics,
                         ))
                         .with_children(|parent| {
                             parent.spawn(TextBundle::from_section(
-                                "Exit",
-                                TextStyle {
-                                    ..Default::default()
+                                "Statistics",
+                                palette.text_style(),
+                            ));
+                        });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..Palette::button_style()
                                 },
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::Editor,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Board Editor",
+                                palette.text_style(),
                             ));
                         });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                background_color: BackgroundColor(palette.danger),
+                                ..palette.button_bundle()
+                            },
+                            MenuButtonAction::Exit,
+                            ThemeColor::Danger,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section("Exit", palette.text_style()));
+                        });
                 });
         });
 }
 
+/// "Continue" and a load button for every slot holding a saved game
+fn spawn_saves_row(parent: &mut ChildBuilder, palette: &Palette, saves: &[Slot]) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for slot in saves {
+                let label = match slot {
+                    Slot::Auto => "Continue".to_string(),
+                    Slot::Manual(slot) => format!("Load {}", slot),
+                };
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                margin: UiRect::all(Val::Px(5.0)),
+                                ..Palette::button_style()
+                            },
+                            ..palette.button_bundle()
+                        },
+                        MenuButtonAction::Load(*slot),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, palette.text_style()));
+                    });
+            }
+        });
+}
+
+/// Engine toggle and search depth stepper of one side
+fn spawn_engine_row(
+    parent: &mut ChildBuilder,
+    palette: &Palette,
+    side: PlayerChoice,
+    settings: &GameSettings,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..Palette::button_style()
+                        },
+                        ..palette.button_bundle()
+                    },
+                    MenuButtonAction::ToggleEngine(side),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            settings.label(SettingText::Engine(side)),
+                            palette.text_style(),
+                        ),
+                        SettingText::Engine(side),
+                    ));
+                });
+            spawn_stepper(parent, palette, Setting::Depth(side), settings);
+        });
+}
+
+/// A row of "-", the setting's value and "+"
+fn spawn_stepper(
+    parent: &mut ChildBuilder,
+    palette: &Palette,
+    setting: Setting,
+    settings: &GameSettings,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                margin: UiRect::all(Val::Px(5.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (label, step) in [("-", -1), ("+", 1)] {
+                if step > 0 {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            settings.label(SettingText::Stepper(setting)),
+                            palette.text_style(),
+                        ),
+                        SettingText::Stepper(setting),
+                    ));
+                }
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(10.0))
+                                    .with_top(Val::Px(2.0))
+                                    .with_bottom(Val::Px(2.0)),
+                                ..Palette::button_style()
+                            },
+                            ..palette.button_bundle()
+                        },
+                        MenuButtonAction::Adjust(setting, step),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, palette.text_style()));
+                    });
+            }
+        });
+}
+
 fn menu_action(
-    interaction_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>),
-    >,
+    interaction_query: ButtonInteractions<MenuButtonAction>,
     mut app_state: ResMut<NextState<AppState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut exit: EventWriter<bevy::app::AppExit>,
+    mut settings: ResMut<GameSettings>,
+    mut setting_texts: Query<(&mut Text, &SettingText)>,
     mut commands: Commands,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
@@ -182,32 +576,87 @@ fn menu_action(
             match menu_button_action {
                 MenuButtonAction::PlayX => {
                     info!("Playing as X");
-                    commands.insert_resource(GameData {
-                        player: PlayerChoice::X,
-                        grid: Grid::new(3, 3),
-                        moves: 0,
-                        won: false,
-                    });
+                    settings.side = PlayerChoice::X;
+                    match settings.match_score(PlayerChoice::X) {
+                        Some(score) => commands.insert_resource(score),
+                        None => commands.remove_resource::<MatchScore>(),
+                    }
+                    commands.insert_resource(GameData::new(
+                        settings.grid(),
+                        PlayerChoice::X,
+                        settings.variant,
+                        settings.mode,
+                        settings.engines,
+                    ));
                     menu_state.set(MenuState::InTransition);
                     app_state.set(AppState::InGame);
                 }
                 MenuButtonAction::PlayO => {
                     info!("Playing as O");
-                    commands.insert_resource(GameData {
-                        player: PlayerChoice::O,
-                        grid: Grid::new(3, 3),
-                        moves: 0,
-                        won: false,
-                    });
+                    settings.side = PlayerChoice::O;
+                    match settings.match_score(PlayerChoice::O) {
+                        Some(score) => commands.insert_resource(score),
+                        None => commands.remove_resource::<MatchScore>(),
+                    }
+                    commands.insert_resource(GameData::new(
+                        settings.grid(),
+                        PlayerChoice::O,
+                        settings.variant,
+                        settings.mode,
+                        settings.engines,
+                    ));
                     menu_state.set(MenuState::InTransition);
                     app_state.set(AppState::InGame);
                 }
+                MenuButtonAction::ToggleVariant => {
+                    settings.variant = settings.variant.next();
+                }
+                MenuButtonAction::ToggleMode => {
+                    settings.mode = settings.mode.next();
+                }
+                MenuButtonAction::ToggleMatch => {
+                    settings.best_of = settings.next_best_of();
+                }
+                MenuButtonAction::ToggleEngine(side) => {
+                    let engine = &mut settings.engines[*side as usize].engine;
+                    *engine = engine.next();
+                }
+                MenuButtonAction::Adjust(setting, step) => {
+                    settings.adjust(*setting, *step);
+                }
+                MenuButtonAction::Load(slot) => {
+                    // A save that can't be read keeps the player in the menu
+                    if let Some(game_data) = slot.load() {
+                        info!("Resuming the game from {}", slot);
+                        commands.insert_resource(game_data);
+                        commands.remove_resource::<MatchScore>();
+                        menu_state.set(MenuState::InTransition);
+                        app_state.set(AppState::InGame);
+                    }
+                }
+                MenuButtonAction::Editor => {
+                    menu_state.set(MenuState::InTransition);
+                    app_state.set(AppState::InEditor);
+                }
+                MenuButtonAction::Settings => {
+                    menu_state.set(MenuState::InTransition);
+                    app_state.set(AppState::InSettings);
+                }
+                MenuButtonAction::Statistics => {
+                    menu_state.set(MenuState::InTransition);
+                    app_state.set(AppState::InStats);
+                }
                 MenuButtonAction::Exit => {
                     exit.send(bevy::app::AppExit::Success);
                 }
             }
         }
     }
+    if settings.is_changed() {
+        for (mut text, label) in &mut setting_texts {
+            text.sections[0].value = settings.label(*label);
+        }
+    }
 }
 
 // fn button_hover_system(
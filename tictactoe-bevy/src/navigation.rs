@@ -0,0 +1,473 @@
+use bevy::{
+    input::gamepad::{GamepadButton, GamepadButtonType, Gamepads},
+    prelude::*,
+    sprite::MaterialMesh2dBundle,
+    ui::UiSystem,
+};
+
+use crate::{
+    layout::BoardLayout,
+    theme::{Theme, ThemeColor},
+    Pressed, Tile, WinPossibilities,
+};
+
+pub struct NavigationPlugin;
+
+/// One step of keyboard or gamepad navigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nav {
+    /// Arrow keys and D-pad, in columns and rows with rows counting downwards
+    Move(IVec2),
+    /// Numpad digit, laid out like the tiles of a 3x3 board with 7-8-9 as the
+    /// top row. Holds the index of the tile.
+    Cell(u32),
+    /// Tab and the shoulder buttons walk through the buttons on screen
+    Next,
+    Previous,
+    /// Enter, Space or A
+    Activate,
+    /// Escape or B go back from the buttons to the board
+    Cancel,
+}
+
+/// Tile under the keyboard cursor, which shows once a key or gamepad button
+/// was used on the board
+#[derive(Resource, Default)]
+struct BoardCursor {
+    index: u32,
+    visible: bool,
+}
+
+/// Button picked with the keyboard or a gamepad
+#[derive(Resource, Default)]
+struct ButtonFocus {
+    entity: Option<Entity>,
+    /// Button pressed through navigation, released again on the next frame
+    pressed: Option<Entity>,
+}
+
+#[derive(Component)]
+struct CursorHighlight;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoardCursor>()
+            .init_resource::<ButtonFocus>()
+            .add_systems(Startup, spawn_cursor_highlight)
+            // Runs before `Update`, so the systems reacting to button
+            // presses see a navigation press exactly once
+            .add_systems(PreUpdate, navigate.after(UiSystem::Focus))
+            .add_systems(
+                Update,
+                (
+                    show_cursor,
+                    show_button_focus
+                        .run_if(resource_changed::<ButtonFocus>.or_else(resource_changed::<Theme>)),
+                ),
+            );
+    }
+}
+
+fn spawn_cursor_highlight(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    theme: Res<Theme>,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(Rectangle::default()).into(),
+            material: materials.add(theme.palette().highlight),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        CursorHighlight,
+        ThemeColor::Highlight,
+    ));
+}
+
+fn read_navigation(
+    keys: &ButtonInput<KeyCode>,
+    gamepads: &Gamepads,
+    pad_buttons: &ButtonInput<GamepadButton>,
+) -> Vec<Nav> {
+    let mut navs = Vec::new();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (key, nav) in [
+        (KeyCode::ArrowUp, Nav::Move(IVec2::NEG_Y)),
+        (KeyCode::ArrowDown, Nav::Move(IVec2::Y)),
+        (KeyCode::ArrowLeft, Nav::Move(IVec2::NEG_X)),
+        (KeyCode::ArrowRight, Nav::Move(IVec2::X)),
+        (KeyCode::Numpad7, Nav::Cell(0)),
+        (KeyCode::Numpad8, Nav::Cell(1)),
+        (KeyCode::Numpad9, Nav::Cell(2)),
+        (KeyCode::Numpad4, Nav::Cell(3)),
+        (KeyCode::Numpad5, Nav::Cell(4)),
+        (KeyCode::Numpad6, Nav::Cell(5)),
+        (KeyCode::Numpad1, Nav::Cell(6)),
+        (KeyCode::Numpad2, Nav::Cell(7)),
+        (KeyCode::Numpad3, Nav::Cell(8)),
+        (KeyCode::Tab, if shift { Nav::Previous } else { Nav::Next }),
+        (KeyCode::Enter, Nav::Activate),
+        (KeyCode::NumpadEnter, Nav::Activate),
+        (KeyCode::Space, Nav::Activate),
+        (KeyCode::Escape, Nav::Cancel),
+    ] {
+        if keys.just_pressed(key) {
+            navs.push(nav);
+        }
+    }
+    for gamepad in gamepads.iter() {
+        for (button, nav) in [
+            (GamepadButtonType::DPadUp, Nav::Move(IVec2::NEG_Y)),
+            (GamepadButtonType::DPadDown, Nav::Move(IVec2::Y)),
+            (GamepadButtonType::DPadLeft, Nav::Move(IVec2::NEG_X)),
+            (GamepadButtonType::DPadRight, Nav::Move(IVec2::X)),
+            (GamepadButtonType::RightTrigger, Nav::Next),
+            (GamepadButtonType::LeftTrigger, Nav::Previous),
+            (GamepadButtonType::South, Nav::Activate),
+            (GamepadButtonType::East, Nav::Cancel),
+        ] {
+            if pad_buttons.just_pressed(GamepadButton::new(gamepad, button)) {
+                navs.push(nav);
+            }
+        }
+    }
+    navs
+}
+
+/// Buttons on screen in reading order
+fn buttons_in_order(
+    buttons: &Query<(Entity, &mut Interaction, &GlobalTransform), With<Button>>,
+) -> Vec<Entity> {
+    let mut ordered: Vec<(Entity, Vec3)> = buttons
+        .iter()
+        .map(|(entity, _, transform)| (entity, transform.translation()))
+        .collect();
+    ordered.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    ordered.into_iter().map(|(entity, _)| entity).collect()
+}
+
+/// Moves the button focus `step` buttons on, wrapping around at both ends
+fn step_focus(focus: &mut ButtonFocus, ordered: &[Entity], step: isize) {
+    if ordered.is_empty() {
+        focus.entity = None;
+        return;
+    }
+    let len = ordered.len() as isize;
+    let next = match focus
+        .entity
+        .and_then(|entity| ordered.iter().position(|&other| other == entity))
+    {
+        Some(current) => (current as isize + step).rem_euclid(len),
+        None if step < 0 => len - 1,
+        None => 0,
+    };
+    focus.entity = Some(ordered[next as usize]);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigate(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    tiles: Query<(Entity, &Tile)>,
+    layout: Option<Res<BoardLayout>>,
+    mut buttons: Query<(Entity, &mut Interaction, &GlobalTransform), With<Button>>,
+    win: Option<Res<WinPossibilities>>,
+    mut cursor: ResMut<BoardCursor>,
+    mut focus: ResMut<ButtonFocus>,
+    mut commands: Commands,
+) {
+    // Only touch the focus when needed, its change detection drives the
+    // outline
+    if let Some(entity) = focus.pressed {
+        focus.pressed = None;
+        if let Ok((_, mut interaction, _)) = buttons.get_mut(entity) {
+            if *interaction == Interaction::Pressed {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+    // Buttons go away whenever the screen changes
+    if focus.entity.is_some_and(|entity| !buttons.contains(entity)) {
+        focus.entity = None;
+    }
+    // The mouse takes over until the next key press
+    if mouse.just_pressed(MouseButton::Left) {
+        cursor.visible = false;
+        focus.entity = None;
+    }
+
+    let board = layout
+        .filter(|_| !tiles.is_empty() && win.is_none())
+        .map(|layout| (layout.rows as i32, layout.cols as i32));
+    if let Some((rows, cols)) = board {
+        if cursor.index >= (rows * cols) as u32 {
+            cursor.index = 0;
+        }
+    }
+    let press_tile = |index: u32, commands: &mut Commands| {
+        if let Some((entity, _)) = tiles.iter().find(|(_, tile)| tile.index == index) {
+            commands.trigger_targets(Pressed, entity);
+        }
+    };
+
+    for nav in read_navigation(&keys, &gamepads, &pad_buttons) {
+        let on_board = focus.entity.is_none() && board.is_some();
+        match nav {
+            Nav::Move(direction) if on_board => {
+                let (rows, cols) = board.unwrap();
+                if cursor.visible {
+                    let index = cursor.index as i32;
+                    let row = (index / cols + direction.y).clamp(0, rows - 1);
+                    let col = (index % cols + direction.x).clamp(0, cols - 1);
+                    cursor.index = (row * cols + col) as u32;
+                }
+                cursor.visible = true;
+            }
+            Nav::Move(direction) => {
+                let step = if direction.x + direction.y < 0 { -1 } else { 1 };
+                step_focus(&mut focus, &buttons_in_order(&buttons), step);
+            }
+            Nav::Cell(index) => {
+                if on_board && board == Some((3, 3)) {
+                    cursor.index = index;
+                    cursor.visible = true;
+                    press_tile(index, &mut commands);
+                }
+            }
+            Nav::Next => step_focus(&mut focus, &buttons_in_order(&buttons), 1),
+            Nav::Previous => step_focus(&mut focus, &buttons_in_order(&buttons), -1),
+            Nav::Activate => {
+                if let Some(entity) = focus.entity {
+                    if let Ok((_, mut interaction, _)) = buttons.get_mut(entity) {
+                        *interaction = Interaction::Pressed;
+                        focus.pressed = Some(entity);
+                    }
+                } else if on_board && cursor.visible {
+                    press_tile(cursor.index, &mut commands);
+                } else if on_board {
+                    cursor.visible = true;
+                }
+            }
+            Nav::Cancel => focus.entity = None,
+        }
+    }
+}
+
+/// Frames the tile under the cursor while the board has the focus
+fn show_cursor(
+    cursor: Res<BoardCursor>,
+    focus: Res<ButtonFocus>,
+    tiles: Query<&Tile>,
+    layout: Option<Res<BoardLayout>>,
+    mut highlight: Query<(&mut Transform, &mut Visibility), With<CursorHighlight>>,
+) {
+    let tile = tiles
+        .iter()
+        .find(|tile| tile.index == cursor.index)
+        .zip(layout)
+        .filter(|_| cursor.visible && focus.entity.is_none());
+    for (mut transform, mut visibility) in &mut highlight {
+        match tile {
+            Some((tile, ref layout)) => {
+                // Slightly larger than the tile, so only a frame shows around it
+                *transform = Transform::default()
+                    .with_scale(Vec3::splat(layout.step))
+                    .with_translation(tile.pos.extend(0.05));
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn show_button_focus(
+    focus: Res<ButtonFocus>,
+    theme: Res<Theme>,
+    buttons: Query<(Entity, Has<Outline>), With<Button>>,
+    mut commands: Commands,
+) {
+    for (entity, outlined) in &buttons {
+        if Some(entity) == focus.entity {
+            commands.entity(entity).insert(Outline::new(
+                Val::Px(3.0),
+                Val::Px(2.0),
+                theme.palette().highlight,
+            ));
+        } else if outlined {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::gamepad::{
+        gamepad_connection_system, Gamepad, GamepadAxis, GamepadConnection, GamepadConnectionEvent,
+        GamepadInfo,
+    };
+
+    use super::*;
+
+    /// Indices of the tiles pressed so far
+    #[derive(Resource, Default)]
+    struct PressedTiles(Vec<u32>);
+
+    /// Headless app running only the navigation, on a board of `rows` by
+    /// `cols` tiles, with one button on screen and a gamepad connected
+    fn board_app(rows: usize, cols: usize) -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<Axis<GamepadButton>>()
+            .init_resource::<BoardCursor>()
+            .init_resource::<ButtonFocus>()
+            .init_resource::<PressedTiles>()
+            .insert_resource(BoardLayout {
+                rows,
+                cols,
+                step: 100.0,
+                center: Vec2::ZERO,
+            })
+            .add_event::<GamepadConnectionEvent>()
+            .add_systems(PreUpdate, gamepad_connection_system)
+            .add_systems(Update, navigate)
+            .observe(
+                |trigger: Trigger<Pressed>,
+                 tiles: Query<&Tile>,
+                 mut pressed: ResMut<PressedTiles>| {
+                    pressed.0.push(tiles.get(trigger.entity()).unwrap().index);
+                },
+            );
+        for index in 0..(rows * cols) as u32 {
+            app.world_mut().spawn(Tile {
+                pos: Vec2::ZERO,
+                size: 1.0,
+                index,
+            });
+        }
+        app.world_mut()
+            .spawn((Button, Interaction::None, GlobalTransform::default()));
+        app.world_mut().send_event(GamepadConnectionEvent::new(
+            Gamepad::new(0),
+            GamepadConnection::Connected(GamepadInfo {
+                name: "Pad".to_string(),
+            }),
+        ));
+        app.update();
+        app
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        app.update();
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(key);
+        keys.clear();
+    }
+
+    fn cursor(app: &App) -> u32 {
+        let cursor = app.world().resource::<BoardCursor>();
+        assert!(cursor.visible);
+        cursor.index
+    }
+
+    #[test]
+    fn cursor_moves_within_non_square_boards() {
+        let mut app = board_app(3, 5);
+        // The first key only shows the cursor
+        press(&mut app, KeyCode::ArrowRight);
+        assert_eq!(cursor(&app), 0);
+        for _ in 0..6 {
+            press(&mut app, KeyCode::ArrowRight);
+        }
+        assert_eq!(cursor(&app), 4);
+        for _ in 0..4 {
+            press(&mut app, KeyCode::ArrowDown);
+        }
+        assert_eq!(cursor(&app), 14);
+        press(&mut app, KeyCode::ArrowLeft);
+        press(&mut app, KeyCode::ArrowUp);
+        assert_eq!(cursor(&app), 8);
+
+        let mut app = board_app(4, 3);
+        press(&mut app, KeyCode::ArrowDown);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::ArrowDown);
+        }
+        press(&mut app, KeyCode::ArrowRight);
+        assert_eq!(cursor(&app), 10);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.world().resource::<PressedTiles>().0, vec![10]);
+    }
+
+    #[test]
+    fn numpad_presses_tiles_of_3x3_boards_only() {
+        let mut app = board_app(3, 3);
+        press(&mut app, KeyCode::Numpad7);
+        press(&mut app, KeyCode::Numpad5);
+        press(&mut app, KeyCode::Numpad3);
+        assert_eq!(app.world().resource::<PressedTiles>().0, vec![0, 4, 8]);
+        assert_eq!(cursor(&app), 8);
+
+        let mut app = board_app(4, 4);
+        press(&mut app, KeyCode::Numpad7);
+        press(&mut app, KeyCode::Numpad3);
+        assert!(app.world().resource::<PressedTiles>().0.is_empty());
+        assert!(!app.world().resource::<BoardCursor>().visible);
+    }
+
+    fn press_pad(app: &mut App, button: GamepadButtonType) {
+        let button = GamepadButton::new(Gamepad::new(0), button);
+        app.world_mut()
+            .resource_mut::<ButtonInput<GamepadButton>>()
+            .press(button);
+        app.update();
+        let mut buttons = app.world_mut().resource_mut::<ButtonInput<GamepadButton>>();
+        buttons.release(button);
+        buttons.clear();
+    }
+
+    #[test]
+    fn escape_and_b_return_the_focus_to_the_board() {
+        let mut app = board_app(3, 3);
+        press(&mut app, KeyCode::ArrowRight);
+        press(&mut app, KeyCode::Tab);
+        assert!(app.world().resource::<ButtonFocus>().entity.is_some());
+        // Arrows walk the buttons while one has the focus
+        press(&mut app, KeyCode::ArrowRight);
+        assert_eq!(cursor(&app), 0);
+
+        press(&mut app, KeyCode::Escape);
+        assert_eq!(app.world().resource::<ButtonFocus>().entity, None);
+        press(&mut app, KeyCode::ArrowRight);
+        assert_eq!(cursor(&app), 1);
+
+        press(&mut app, KeyCode::Tab);
+        assert!(app.world().resource::<ButtonFocus>().entity.is_some());
+        press_pad(&mut app, GamepadButtonType::East);
+        assert_eq!(app.world().resource::<ButtonFocus>().entity, None);
+        press_pad(&mut app, GamepadButtonType::DPadRight);
+        assert_eq!(cursor(&app), 2);
+    }
+
+    #[test]
+    fn focus_wraps_around() {
+        let ordered: Vec<Entity> = (1..4).map(Entity::from_raw).collect();
+        let mut focus = ButtonFocus::default();
+        step_focus(&mut focus, &ordered, -1);
+        assert_eq!(focus.entity, Some(ordered[2]));
+        step_focus(&mut focus, &ordered, 1);
+        assert_eq!(focus.entity, Some(ordered[0]));
+        step_focus(&mut focus, &[], 1);
+        assert_eq!(focus.entity, None);
+    }
+}
@@ -0,0 +1,115 @@
+use tictactoe_logic::{
+    grid::{FieldStates, Grid},
+    rules::{Classic, Placement, Rules, Status, Wild},
+};
+
+use crate::{PlayerChoice, Variant};
+
+/// Position of any variant the game offers. Everything else in the frontend
+/// only goes through [`Rules`], so a new variant needs an arm here and in
+/// [`Variant::position`].
+#[derive(Clone, Debug)]
+pub enum Position {
+    Classic(Classic),
+    Wild(Wild),
+}
+
+/// Runs `$body` with `$rules` bound to the rules of whichever variant is played
+macro_rules! dispatch {
+    ($position:expr, $rules:ident => $body:expr) => {
+        match $position {
+            Position::Classic($rules) => $body,
+            Position::Wild($rules) => $body,
+        }
+    };
+}
+
+impl Variant {
+    /// Position of this variant on `grid` with `to_move` to move
+    pub fn position(&self, grid: Grid, to_move: PlayerChoice) -> Position {
+        let to_move = to_move.to_field_states();
+        match self {
+            Variant::Classic => Position::Classic(Classic::new(grid, to_move)),
+            Variant::Wild => Position::Wild(Wild::new(grid, to_move)),
+        }
+    }
+}
+
+impl Position {
+    pub fn grid(&self) -> &Grid {
+        dispatch!(self, rules => rules.grid())
+    }
+
+    /// Whether the side to move can place either symbol
+    pub fn picks_symbol(&self) -> bool {
+        let moves = self.legal_moves();
+        moves.iter().any(|played| {
+            moves
+                .iter()
+                .any(|other| other.index == played.index && other != played)
+        })
+    }
+}
+
+impl Rules for Position {
+    type Move = Placement;
+
+    fn legal_moves(&self) -> Vec<Placement> {
+        dispatch!(self, rules => rules.legal_moves())
+    }
+
+    fn apply(&mut self, played: Placement) {
+        dispatch!(self, rules => rules.apply(played))
+    }
+
+    fn undo(&mut self, played: Placement) {
+        dispatch!(self, rules => rules.undo(played))
+    }
+
+    fn status(&self) -> Status {
+        dispatch!(self, rules => rules.status())
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        dispatch!(self, rules => rules.side_to_move())
+    }
+
+    fn heuristic(&self, player: FieldStates) -> i32 {
+        dispatch!(self, rules => rules.heuristic(player))
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        dispatch!(self, rules => rules.default_depth())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameData, Mode, WinPossibilities};
+
+    #[test]
+    fn games_follow_the_rules_of_their_variant() {
+        let classic = Variant::Classic.position(Grid::new(3, 3), PlayerChoice::O);
+        assert_eq!(classic.side_to_move(), FieldStates::Player2);
+        assert!(!classic.picks_symbol());
+        assert!(Variant::Wild
+            .position(Grid::new(3, 3), PlayerChoice::X)
+            .picks_symbol());
+
+        // X completes a line of O in a wild game, and wins with it
+        let mut game_data = GameData::new(
+            "2 2 0\n1 0 0\n0 1 0".parse().unwrap(),
+            PlayerChoice::X,
+            Variant::Wild,
+            Mode::Hotseat,
+            Default::default(),
+        );
+        game_data.symbol = PlayerChoice::O;
+        assert_eq!(game_data.who_moves(), PlayerChoice::X);
+        game_data.make_move(2).unwrap();
+        assert_eq!(game_data.history[0].value, FieldStates::Player2);
+        assert_eq!(game_data.check_game_state(), WinPossibilities::XWon);
+        assert!(game_data.make_move(4).is_err());
+    }
+}
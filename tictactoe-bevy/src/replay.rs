@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use tictactoe_logic::grid::Grid;
+
+use crate::{
+    despawn_screen, spawn_symbol, spawn_tiles,
+    stats::GameRecord,
+    theme::{Palette, Theme},
+    AppState, ButtonInteractions, Pressed, Symbol, Tile,
+};
+
+pub struct ReplayPlugin;
+
+/// Recorded game shown move by move
+#[derive(Resource)]
+pub struct Replay {
+    game: GameRecord,
+    /// Number of moves on the board
+    shown: usize,
+}
+
+impl Replay {
+    pub fn new(game: GameRecord) -> Self {
+        Self { game, shown: 0 }
+    }
+
+    /// Board before the first move
+    fn start(&self) -> Grid {
+        let mut grid =
+            Grid::new(self.game.rows, self.game.cols).with_win_length(self.game.win_length);
+        for (index, symbol) in self.game.start.iter().enumerate() {
+            if let Some(symbol) = symbol {
+                grid.set_elem(index, symbol.to_field_states());
+            }
+        }
+        grid
+    }
+
+    fn label(&self) -> String {
+        let result = if self.shown == self.game.moves.len() {
+            format!(", {}", self.game.outcome)
+        } else {
+            String::new()
+        };
+        format!("Move {} of {}{}", self.shown, self.game.moves.len(), result)
+    }
+}
+
+#[derive(Component, Clone)]
+struct OnReplayScreen;
+
+#[derive(Component)]
+struct ReplayText;
+
+#[derive(Component, Clone, Copy)]
+enum ReplayButtonAction {
+    First,
+    Previous,
+    Next,
+    Last,
+    Back,
+}
+
+impl std::fmt::Display for ReplayButtonAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayButtonAction::First => write!(f, "First"),
+            ReplayButtonAction::Previous => write!(f, "Previous"),
+            ReplayButtonAction::Next => write!(f, "Next"),
+            ReplayButtonAction::Last => write!(f, "Last"),
+            ReplayButtonAction::Back => write!(f, "Back"),
+        }
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InReplay), setup_replay)
+            .add_systems(Update, replay_action.run_if(in_state(AppState::InReplay)))
+            .add_systems(OnExit(AppState::InReplay), despawn_screen::<OnReplayScreen>);
+    }
+}
+
+fn setup_replay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut replay: ResMut<Replay>,
+    theme: Res<Theme>,
+) {
+    let palette = theme.palette();
+    replay.shown = 0;
+    // Tiles of a replay don't react to clicks
+    let mut observer = Observer::new(|_: Trigger<Pressed>| {});
+    let grid = replay.start();
+    let tiles = spawn_tiles(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &theme,
+        &mut observer,
+        OnReplayScreen,
+        &grid,
+    );
+    for (tile, symbol) in tiles.iter().zip(&replay.game.start) {
+        if let Some(symbol) = symbol {
+            spawn_symbol(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &theme,
+                tile,
+                *symbol,
+                OnReplayScreen,
+            );
+        }
+    }
+
+    commands.spawn((
+        TextBundle::from_section(replay.label(), palette.text_style()).with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..Default::default()
+        }),
+        ReplayText,
+        OnReplayScreen,
+    ));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnReplayScreen,
+        ))
+        .with_children(|parent| {
+            for action in [
+                ReplayButtonAction::First,
+                ReplayButtonAction::Previous,
+                ReplayButtonAction::Next,
+                ReplayButtonAction::Last,
+                ReplayButtonAction::Back,
+            ] {
+                spawn_button(parent, palette, action);
+            }
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, palette: &Palette, action: ReplayButtonAction) {
+    parent
+        .spawn((palette.button_bundle(), action))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                action.to_string(),
+                palette.text_style(),
+            ));
+        });
+}
+
+/// Steps through the recorded moves, placing and lifting one symbol at a time
+#[allow(clippy::too_many_arguments)]
+fn replay_action(
+    interaction_query: ButtonInteractions<ReplayButtonAction>,
+    mut replay: ResMut<Replay>,
+    tiles: Query<&Tile>,
+    symbols: Query<(Entity, &Symbol)>,
+    mut texts: Query<&mut Text, With<ReplayText>>,
+    theme: Res<Theme>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(action) = interaction_query.iter().find_map(|(interaction, action)| {
+        (*interaction == Interaction::Pressed).then_some(*action)
+    }) else {
+        return;
+    };
+    let target = match action {
+        ReplayButtonAction::First => 0,
+        ReplayButtonAction::Previous => replay.shown.saturating_sub(1),
+        ReplayButtonAction::Next => (replay.shown + 1).min(replay.game.moves.len()),
+        ReplayButtonAction::Last => replay.game.moves.len(),
+        ReplayButtonAction::Back => {
+            app_state.set(AppState::InStats);
+            return;
+        }
+    };
+    while replay.shown > target {
+        replay.shown -= 1;
+        let (index, _) = replay.game.moves[replay.shown];
+        for (entity, symbol) in &symbols {
+            if symbol.index as usize == index {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+    while replay.shown < target {
+        let (index, symbol) = replay.game.moves[replay.shown];
+        replay.shown += 1;
+        if let Some(tile) = tiles.iter().find(|tile| tile.index as usize == index) {
+            spawn_symbol(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &theme,
+                tile,
+                symbol,
+                OnReplayScreen,
+            );
+        }
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = replay.label();
+    }
+}
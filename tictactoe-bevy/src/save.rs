@@ -0,0 +1,435 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+use tictactoe_logic::{
+    grid::Grid,
+    rules::{Classic, Placement, Rules, Status},
+};
+
+use crate::{
+    config::write_ron,
+    menu::{MAX_SIZE, MIN_SIZE},
+    position::Position,
+    theme::{Palette, Theme},
+    AppState, EngineSettings, GameData, Mode, OnGameScreen, PlayerChoice, Variant, MAX_DEPTH,
+};
+
+pub struct SavePlugin;
+
+/// Version of the saved games written by this build
+const SAVE_VERSION: u32 = 1;
+
+/// Number of slots the player can save to by hand
+pub const SLOTS: u8 = 3;
+
+/// Where a game is saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    /// Written when the game is closed in the middle of a game, resumed
+    /// through "Continue"
+    Auto,
+    /// Saved and loaded by hand, numbered from 1
+    Manual(u8),
+}
+
+impl Slot {
+    /// Every slot, the autosave first
+    pub fn all() -> impl Iterator<Item = Slot> {
+        std::iter::once(Slot::Auto).chain((1..=SLOTS).map(Slot::Manual))
+    }
+
+    /// File of the slot in the platform's data directory,
+    /// `$XDG_DATA_HOME` or `~/.local/share` on Linux. There is none on the
+    /// web.
+    fn path(self) -> Option<PathBuf> {
+        let name = match self {
+            Slot::Auto => "autosave.ron".to_string(),
+            Slot::Manual(slot) => format!("slot-{}.ron", slot),
+        };
+        dirs::data_dir().map(|dir| dir.join("tictactoe").join("saves").join(name))
+    }
+
+    pub fn exists(self) -> bool {
+        self.path().is_some_and(|path| path.is_file())
+    }
+
+    /// Game saved in the slot, `None` if there is none or it is corrupt. The
+    /// autosave is used up by loading it, closing the game again saves anew.
+    pub fn load(self) -> Option<GameData> {
+        let text = fs::read_to_string(self.path()?).ok()?;
+        let game = ron::from_str::<SavedGame>(&text)
+            .map_err(|error| warn!("Ignoring corrupt save in {}: {}", self, error))
+            .ok()?
+            .restore();
+        if game.is_none() {
+            warn!("Ignoring invalid save in {}", self);
+        } else if self == Slot::Auto {
+            self.delete();
+        }
+        game
+    }
+
+    fn save(self, game_data: &GameData) -> Result<(), String> {
+        let path = self.path().ok_or("no data directory")?;
+        write_ron(&path, &SavedGame::new(game_data))
+    }
+
+    fn delete(self) {
+        if let Some(path) = self.path().filter(|path| path.is_file()) {
+            if let Err(error) = fs::remove_file(&path) {
+                warn!("Couldn't delete {}: {}", path.display(), error);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Slot::Auto => write!(f, "autosave"),
+            Slot::Manual(slot) => write!(f, "slot {}", slot),
+        }
+    }
+}
+
+/// Everything needed to set up [`GameData`] again. The grid is kept as its
+/// fields, games from the board editor don't start out empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedGame {
+    version: u32,
+    player: PlayerChoice,
+    rows: usize,
+    cols: usize,
+    win_length: usize,
+    /// Symbol on every field, row by row
+    fields: Vec<Option<PlayerChoice>>,
+    /// Positions from the board editor may have either side to move
+    to_move: PlayerChoice,
+    moves: u32,
+    won: bool,
+    variant: Variant,
+    symbol: PlayerChoice,
+    mode: Mode,
+    engines: [EngineSettings; 2],
+    /// Field and symbol of every move, as in [`GameData::history`]
+    history: Vec<(usize, PlayerChoice)>,
+    undone: Vec<(usize, PlayerChoice)>,
+    hints: u32,
+}
+
+impl SavedGame {
+    fn new(game_data: &GameData) -> Self {
+        let (rows, cols) = game_data.grid().size();
+        let placements = |placements: &[Placement]| {
+            placements
+                .iter()
+                .filter_map(|placement| {
+                    PlayerChoice::from_field_states(placement.value)
+                        .map(|symbol| (placement.index, symbol))
+                })
+                .collect()
+        };
+        Self {
+            version: SAVE_VERSION,
+            player: game_data.player,
+            rows,
+            cols,
+            win_length: game_data.grid().win_length(),
+            fields: (0..rows * cols)
+                .map(|index| game_data.symbol_at(index as u32))
+                .collect(),
+            to_move: game_data.who_moves(),
+            moves: game_data.moves,
+            won: game_data.won,
+            variant: game_data.variant,
+            symbol: game_data.symbol,
+            mode: game_data.mode,
+            engines: game_data.engines,
+            history: placements(&game_data.history),
+            undone: placements(&game_data.undone),
+            hints: game_data.hints,
+        }
+    }
+
+    /// The saved game, `None` if the save doesn't describe a board the menu
+    /// could have set up or a game that could have been played on it
+    fn restore(self) -> Option<GameData> {
+        let size = self.rows * self.cols;
+        let valid = self.version == SAVE_VERSION
+            && (MIN_SIZE..=MAX_SIZE).contains(&self.rows)
+            && (MIN_SIZE..=MAX_SIZE).contains(&self.cols)
+            && (MIN_SIZE..=self.rows.min(self.cols)).contains(&self.win_length)
+            && self.fields.len() == size
+            && self
+                .history
+                .iter()
+                .chain(&self.undone)
+                .all(|(index, _)| *index < size);
+        if !valid {
+            return None;
+        }
+        let mut grid = Grid::new(self.rows, self.cols).with_win_length(self.win_length);
+        for (index, symbol) in self.fields.iter().enumerate() {
+            if let Some(symbol) = symbol {
+                grid.set_elem(index, symbol.to_field_states());
+            }
+        }
+        let placements = |placements: Vec<(usize, PlayerChoice)>| -> Vec<Placement> {
+            placements
+                .into_iter()
+                .map(|(index, symbol)| Placement {
+                    index,
+                    value: symbol.to_field_states(),
+                })
+                .collect()
+        };
+        let history = placements(self.history);
+        let undone = placements(self.undone);
+        let position = self.variant.position(grid.clone(), self.to_move);
+        let occupied = self.fields.iter().flatten().count();
+        let valid = replays(&position, &history, &undone)
+            && self.moves as usize == occupied
+            && self.won == (position.status() != Status::Ongoing)
+            // Boards from the editor are only checked for classic games
+            && (self.variant != Variant::Classic
+                || Classic::setup(grid.clone(), self.to_move.to_field_states()).is_ok());
+        if !valid {
+            return None;
+        }
+        let mut engines = self.engines;
+        for engine in &mut engines {
+            engine.depth = engine.depth.clamp(1, MAX_DEPTH);
+        }
+        Some(GameData {
+            position,
+            moves: self.moves,
+            won: self.won,
+            symbol: self.symbol,
+            history,
+            undone,
+            hints: self.hints,
+            ..GameData::new(grid, self.player, self.variant, self.mode, engines)
+        })
+    }
+}
+
+/// Whether `history` could have been played to reach `position`, and the
+/// moves in `undone` could be played again from it. The moves are taken back
+/// off the board, then replayed by the rules from where they started.
+fn replays(position: &Position, history: &[Placement], undone: &[Placement]) -> bool {
+    let mut start = position.clone();
+    for placement in history.iter().rev() {
+        if start.grid().get_elem(placement.index) != Some(&placement.value) {
+            return false;
+        }
+        start.undo(*placement);
+    }
+    // The last undone move is the first to be played again
+    let mut replayed = start;
+    for placement in history.iter().chain(undone.iter().rev()) {
+        if replayed.status() != Status::Ongoing || !replayed.legal_moves().contains(placement) {
+            return false;
+        }
+        replayed.apply(*placement);
+    }
+    true
+}
+
+/// Button saving the running game to a slot
+#[derive(Component)]
+struct SaveButton(u8);
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_save_buttons.run_if(|game_data: Option<Res<GameData>>| {
+                game_data.is_some_and(|data| data.mode != Mode::Spectate)
+            }),
+        )
+        .add_systems(
+            Update,
+            save_action
+                .run_if(in_state(AppState::InGame))
+                .run_if(resource_exists::<GameData>),
+        )
+        .add_systems(Last, autosave.run_if(on_event::<AppExit>()));
+    }
+}
+
+fn spawn_save_buttons(mut commands: Commands, theme: Res<Theme>) {
+    let palette = theme.palette();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(110.0),
+                    column_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for slot in 1..=SLOTS {
+                spawn_save_button(parent, palette, slot);
+            }
+        });
+}
+
+fn spawn_save_button(parent: &mut ChildBuilder, palette: &Palette, slot: u8) {
+    parent
+        .spawn((palette.button_bundle(), SaveButton(slot)))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Save {}", slot),
+                palette.text_style(),
+            ));
+        });
+}
+
+fn save_action(
+    interaction_query: Query<(&Interaction, &SaveButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    game_data: Res<GameData>,
+) {
+    for (interaction, SaveButton(slot), children) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let label = match Slot::Manual(*slot).save(&game_data) {
+            Ok(()) => {
+                info!("Saved the game to slot {}", slot);
+                format!("Saved {}", slot)
+            }
+            Err(error) => {
+                warn!("Couldn't save the game to slot {}: {}", slot, error);
+                format!("Save {} failed", slot)
+            }
+        };
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&label);
+        }
+    }
+}
+
+/// Keeps a game closed halfway for "Continue", and drops the autosave once
+/// its game has been finished. Closing from a menu leaves it alone.
+fn autosave(game_data: Option<Res<GameData>>) {
+    match game_data {
+        Some(game_data) if game_data.won => Slot::Auto.delete(),
+        Some(game_data) if game_data.mode != Mode::Spectate => {
+            if let Err(error) = Slot::Auto.save(&game_data) {
+                warn!("Couldn't save the running game: {}", error);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> GameData {
+        GameData {
+            symbol: PlayerChoice::X,
+            hints: 2,
+            ..GameData::new(
+                Grid::new(4, 3).with_win_length(3),
+                PlayerChoice::O,
+                Variant::Wild,
+                Mode::Hotseat,
+                Default::default(),
+            )
+        }
+    }
+
+    #[test]
+    fn saved_games_restore_board_and_history() {
+        let mut game_data = game();
+        for index in [4, 0, 11] {
+            game_data.make_move(index).unwrap();
+        }
+        game_data.undo();
+        let saved = SavedGame::new(&game_data);
+        let text = ron::to_string(&saved).unwrap();
+        let restored = ron::from_str::<SavedGame>(&text)
+            .unwrap()
+            .restore()
+            .unwrap();
+
+        assert_eq!(SavedGame::new(&restored), saved);
+        assert_eq!(restored.grid().size(), (4, 3));
+        assert_eq!(restored.symbol_at(4), Some(PlayerChoice::X));
+        assert_eq!(restored.symbol_at(11), None);
+        assert_eq!(restored.who_moves(), PlayerChoice::X);
+        assert_eq!(restored.undone.len(), 1);
+    }
+
+    #[test]
+    fn the_side_to_move_survives_a_save() {
+        // Set up in the editor with O to move on an empty board
+        let mut game_data = game();
+        game_data.position = Variant::Wild.position(game_data.grid().clone(), PlayerChoice::O);
+        let saved = SavedGame::new(&game_data);
+        assert_eq!(saved.restore().unwrap().who_moves(), PlayerChoice::O);
+    }
+
+    #[test]
+    fn invalid_saves_are_rejected() {
+        let saved = SavedGame::new(&game());
+        let mut short = saved.clone();
+        short.fields.pop();
+        assert!(short.restore().is_none());
+
+        let mut outside = saved.clone();
+        outside.history.push((12, PlayerChoice::X));
+        assert!(outside.restore().is_none());
+
+        let mut newer = saved;
+        newer.version += 1;
+        assert!(newer.restore().is_none());
+    }
+
+    #[test]
+    fn saves_whose_moves_dont_fit_the_board_are_rejected() {
+        let mut game_data = GameData::new(
+            Grid::new(3, 3),
+            PlayerChoice::X,
+            Variant::Classic,
+            Mode::Hotseat,
+            Default::default(),
+        );
+        for index in [4, 0] {
+            game_data.make_move(index).unwrap();
+        }
+        let saved = SavedGame::new(&game_data);
+        assert!(saved.clone().restore().is_some());
+
+        let mut miscounted = saved.clone();
+        miscounted.moves += 1;
+        assert!(miscounted.restore().is_none());
+
+        let mut over = saved.clone();
+        over.won = true;
+        assert!(over.restore().is_none());
+
+        let mut wrong_side = saved.clone();
+        wrong_side.to_move = PlayerChoice::O;
+        assert!(wrong_side.restore().is_none());
+
+        let mut elsewhere = saved.clone();
+        elsewhere.history[1].0 = 8;
+        assert!(elsewhere.restore().is_none());
+
+        let mut taken = saved;
+        taken.undone.push((4, PlayerChoice::X));
+        assert!(taken.restore().is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use tictactoe_logic::grid::Grid;
+
+use crate::{
+    theme::Theme, AppState, ButtonAction, ButtonInteractions, GameData, KIMove, Mode, OnGameScreen,
+    OnPlayAgainScreen, PlayerChoice, Symbol, WinPossibilities,
+};
+
+pub struct ScoreboardPlugin;
+
+/// Match lengths the menu offers, 1 being a single game
+pub const MATCH_LENGTHS: [u32; 4] = [1, 3, 5, 7];
+
+/// Score of a best-of-N match, counted for the first player: the human
+/// against the computer, or whoever sat down first in hotseat games
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct MatchScore {
+    pub best_of: u32,
+    /// Side of the first player in the first game, the sides swap every game
+    first: PlayerChoice,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+}
+
+impl MatchScore {
+    pub fn new(best_of: u32, first: PlayerChoice) -> Self {
+        Self {
+            best_of,
+            first,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+
+    fn played(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Side of the first player in the next game
+    pub fn side(&self) -> PlayerChoice {
+        if self.played().is_multiple_of(2) {
+            self.first
+        } else {
+            self.first.opposite()
+        }
+    }
+
+    fn record(&mut self, win: &WinPossibilities) {
+        let side = self.side();
+        match (win, side) {
+            (WinPossibilities::None, _) => {}
+            (WinPossibilities::Tie, _) => self.draws += 1,
+            (WinPossibilities::XWon, PlayerChoice::X)
+            | (WinPossibilities::OWon, PlayerChoice::O) => self.wins += 1,
+            _ => self.losses += 1,
+        }
+    }
+
+    /// Score once the game on the board counts, which only happens when the
+    /// next one starts so taking moves back can't count a game twice
+    pub fn with_result(&self, win: Option<&WinPossibilities>) -> Self {
+        let mut score = self.clone();
+        if let Some(win) = win {
+            score.record(win);
+        }
+        score
+    }
+
+    /// Whether one player can't be caught any more, or every game was played
+    pub fn is_decided(&self) -> bool {
+        self.wins * 2 > self.best_of
+            || self.losses * 2 > self.best_of
+            || self.played() >= self.best_of
+    }
+
+    /// Result line shown once the match is decided
+    pub fn summary(&self, mode: Mode) -> String {
+        let (first, second) = names(mode);
+        let result = match self.wins.cmp(&self.losses) {
+            std::cmp::Ordering::Greater => format!("{} won the match", first),
+            std::cmp::Ordering::Less => format!("{} won the match", second),
+            std::cmp::Ordering::Equal => "The match is drawn".to_string(),
+        };
+        format!(
+            "{} {} - {}, {} drawn",
+            result, self.wins, self.losses, self.draws
+        )
+    }
+}
+
+/// Names of the first and second player
+fn names(mode: Mode) -> (&'static str, &'static str) {
+    match mode {
+        Mode::Computer => ("You", "Computer"),
+        _ => ("Player 1", "Player 2"),
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_scoreboard.run_if(resource_exists::<MatchScore>),
+        )
+        .add_systems(
+            Update,
+            (next_game, update_scoreboard)
+                .chain()
+                .run_if(in_state(AppState::InGame))
+                .run_if(resource_exists::<MatchScore>)
+                .run_if(resource_exists::<GameData>),
+        );
+    }
+}
+
+fn score_text(score: &MatchScore, win: Option<&WinPossibilities>, mode: Mode) -> String {
+    let (first, second) = names(mode);
+    let game = score.played() + 1;
+    let score = score.with_result(win);
+    format!(
+        "Best of {}, game {}: {} {} - {} {}, {} drawn",
+        score.best_of, game, first, score.wins, score.losses, second, score.draws
+    )
+}
+
+fn spawn_scoreboard(
+    mut commands: Commands,
+    score: Res<MatchScore>,
+    game_data: Res<GameData>,
+    theme: Res<Theme>,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            score_text(&score, None, game_data.mode),
+            theme.palette().text_style(),
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(40.0),
+            ..Default::default()
+        }),
+        ScoreText,
+        OnGameScreen,
+    ));
+}
+
+fn update_scoreboard(
+    score: Res<MatchScore>,
+    win: Option<Res<WinPossibilities>>,
+    game_data: Res<GameData>,
+    mut text_query: Query<&mut Text, With<ScoreText>>,
+) {
+    let value = score_text(&score, win.as_deref(), game_data.mode);
+    for mut text in &mut text_query {
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+/// Counts the finished game and clears the board for the next one, with the
+/// sides swapped
+pub fn next_game(
+    interaction_query: ButtonInteractions<ButtonAction>,
+    mut score: ResMut<MatchScore>,
+    win: Option<Res<WinPossibilities>>,
+    mut game_data: ResMut<GameData>,
+    symbols: Query<Entity, With<Symbol>>,
+    play_again: Query<Entity, With<OnPlayAgainScreen>>,
+    mut commands: Commands,
+) {
+    let pressed = interaction_query.iter().any(|(interaction, action)| {
+        *interaction == Interaction::Pressed && *action == ButtonAction::NextGame
+    });
+    let Some(win) = win.filter(|_| pressed) else {
+        return;
+    };
+    score.record(&win);
+    commands.remove_resource::<WinPossibilities>();
+    for entity in &symbols {
+        commands.entity(entity).despawn();
+    }
+    for entity in &play_again {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (rows, cols) = game_data.grid().size();
+    let win_length = game_data.grid().win_length();
+    game_data.position = game_data.variant.position(
+        Grid::new(rows, cols).with_win_length(win_length),
+        PlayerChoice::X,
+    );
+    game_data.player = score.side();
+    game_data.symbol = score.side();
+    game_data.moves = 0;
+    game_data.won = false;
+    game_data.history.clear();
+    game_data.undone.clear();
+    game_data.hints = 0;
+    if game_data.is_computer_turn() {
+        commands.add(move |world: &mut World| {
+            world.send_event(KIMove);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sides_swap_and_results_count_for_the_first_player() {
+        let mut score = MatchScore::new(3, PlayerChoice::O);
+        assert_eq!(score.side(), PlayerChoice::O);
+        score.record(&WinPossibilities::OWon);
+        assert_eq!(score.side(), PlayerChoice::X);
+        score.record(&WinPossibilities::OWon);
+        assert_eq!((score.wins, score.losses, score.draws), (1, 1, 0));
+        assert!(!score.is_decided());
+
+        // The game on the board only counts for the display
+        let decided = score.with_result(Some(&WinPossibilities::OWon));
+        assert_eq!(score.played(), 2);
+        assert!(decided.is_decided());
+        assert_eq!(
+            decided.summary(Mode::Computer),
+            "You won the match 2 - 1, 0 drawn"
+        );
+    }
+
+    #[test]
+    fn matches_end_early_or_after_every_game() {
+        let mut score = MatchScore::new(5, PlayerChoice::X);
+        score.record(&WinPossibilities::OWon);
+        score.record(&WinPossibilities::XWon);
+        score.record(&WinPossibilities::OWon);
+        assert!(score.is_decided());
+        assert_eq!(
+            score.summary(Mode::Hotseat),
+            "Player 2 won the match 0 - 3, 0 drawn"
+        );
+
+        let mut score = MatchScore::new(3, PlayerChoice::X);
+        for _ in 0..3 {
+            score.record(&WinPossibilities::Tie);
+        }
+        assert!(score.is_decided());
+        assert_eq!(
+            score.summary(Mode::Hotseat),
+            "The match is drawn 0 - 0, 3 drawn"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    animation::AnimationSpeed,
+    audio::{Channel, MusicAvailable, VolumeSettings},
+    despawn_screen,
+    theme::{spawn_button, Palette, Theme, ThemeColor},
+    AppState, ButtonInteractions,
+};
+
+pub struct SettingsPlugin;
+
+/// Change of a volume per press of its "-" or "+" button
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Component)]
+struct OnSettingsScreen;
+
+/// Shows the current value of a setting
+#[derive(Component, Clone, Copy)]
+enum SettingsText {
+    Volume(Channel),
+    Music,
+    Animations,
+    Theme,
+    Colorblind,
+}
+
+impl SettingsText {
+    fn label(self, volume: &VolumeSettings, speed: AnimationSpeed, theme: Theme) -> String {
+        match self {
+            SettingsText::Volume(channel) => {
+                format!("{}: {:.0}%", channel, volume.get(channel) * 100.0)
+            }
+            SettingsText::Music => {
+                format!("Music: {}", if volume.music_on { "on" } else { "off" })
+            }
+            SettingsText::Animations => speed.label(),
+            SettingsText::Theme => format!("Theme: {}", theme.kind),
+            SettingsText::Colorblind => format!(
+                "Colour-blind symbols: {}",
+                if theme.colorblind { "on" } else { "off" }
+            ),
+        }
+    }
+}
+
+/// Bar that sets a volume to the point it is clicked or dragged at
+#[derive(Component)]
+struct VolumeSlider(Channel);
+
+/// Filled part of a [`VolumeSlider`]
+#[derive(Component)]
+struct SliderFill(Channel);
+
+#[derive(Component)]
+enum SettingsButtonAction {
+    Adjust(Channel, f32),
+    ToggleMusic,
+    ToggleAnimations,
+    ToggleTheme,
+    ToggleColorblind,
+    Back,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InSettings), setup_settings)
+            .add_systems(
+                Update,
+                (
+                    settings_action,
+                    drag_sliders,
+                    refresh_settings.run_if(
+                        resource_changed::<VolumeSettings>
+                            .or_else(resource_changed::<AnimationSpeed>)
+                            .or_else(resource_changed::<Theme>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InSettings)),
+            )
+            .add_systems(
+                OnExit(AppState::InSettings),
+                despawn_screen::<OnSettingsScreen>,
+            );
+    }
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    volume: Res<VolumeSettings>,
+    music: Res<MusicAvailable>,
+    speed: Res<AnimationSpeed>,
+    theme: Res<Theme>,
+) {
+    let palette = theme.palette();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnSettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        border_radius: BorderRadius::all(Val::Percent(5.0)),
+                        background_color: BackgroundColor(palette.panel),
+                        border_color: BorderColor(palette.panel_border),
+                        style: Style {
+                            border: UiRect::all(Val::Px(1.0)),
+                            padding: UiRect::all(Val::Px(20.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(10.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    ThemeColor::Panel,
+                ))
+                .with_children(|parent| {
+                    // Without a music file there is nothing the music settings
+                    // could change
+                    let channels = [Channel::Master, Channel::Effects, Channel::Music];
+                    for channel in channels
+                        .into_iter()
+                        .filter(|channel| music.0 || *channel != Channel::Music)
+                    {
+                        spawn_volume_row(parent, palette, channel, &volume, *speed, *theme);
+                    }
+                    for (text, action) in [
+                        (SettingsText::Music, SettingsButtonAction::ToggleMusic),
+                        (
+                            SettingsText::Animations,
+                            SettingsButtonAction::ToggleAnimations,
+                        ),
+                        (SettingsText::Theme, SettingsButtonAction::ToggleTheme),
+                        (
+                            SettingsText::Colorblind,
+                            SettingsButtonAction::ToggleColorblind,
+                        ),
+                    ]
+                    .into_iter()
+                    .filter(|(text, _)| music.0 || !matches!(text, SettingsText::Music))
+                    {
+                        spawn_button(parent, palette, action).with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    text.label(&volume, *speed, *theme),
+                                    palette.text_style(),
+                                ),
+                                text,
+                            ));
+                        });
+                    }
+                    spawn_button(parent, palette, SettingsButtonAction::Back).with_children(
+                        |parent| {
+                            parent.spawn(TextBundle::from_section("Back", palette.text_style()));
+                        },
+                    );
+                });
+        });
+}
+
+/// The volume's value, a "-" button, its slider and a "+" button
+fn spawn_volume_row(
+    parent: &mut ChildBuilder,
+    palette: &Palette,
+    channel: Channel,
+    volume: &VolumeSettings,
+    speed: AnimationSpeed,
+    theme: Theme,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            let text = SettingsText::Volume(channel);
+            parent.spawn((
+                TextBundle::from_section(text.label(volume, speed, theme), palette.text_style())
+                    .with_style(Style {
+                        width: Val::Px(150.0),
+                        ..Default::default()
+                    }),
+                text,
+            ));
+            spawn_button(
+                parent,
+                palette,
+                SettingsButtonAction::Adjust(channel, -VOLUME_STEP),
+            )
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("-", palette.text_style()));
+            });
+            parent
+                .spawn((
+                    NodeBundle {
+                        background_color: BackgroundColor(palette.track),
+                        border_radius: BorderRadius::all(Val::Px(8.0)),
+                        style: Style {
+                            width: Val::Px(200.0),
+                            height: Val::Px(16.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    VolumeSlider(channel),
+                    ThemeColor::Track,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            background_color: BackgroundColor(palette.button),
+                            border_radius: BorderRadius::all(Val::Px(8.0)),
+                            style: Style {
+                                width: Val::Percent(volume.get(channel) * 100.0),
+                                height: Val::Percent(100.0),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        SliderFill(channel),
+                        ThemeColor::Fill,
+                    ));
+                });
+            spawn_button(
+                parent,
+                palette,
+                SettingsButtonAction::Adjust(channel, VOLUME_STEP),
+            )
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("+", palette.text_style()));
+            });
+        });
+}
+
+fn settings_action(
+    interaction_query: ButtonInteractions<SettingsButtonAction>,
+    mut volume: ResMut<VolumeSettings>,
+    mut speed: ResMut<AnimationSpeed>,
+    mut theme: ResMut<Theme>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            SettingsButtonAction::Adjust(channel, step) => {
+                let value = volume.get(*channel) + step;
+                volume.set(*channel, value);
+            }
+            SettingsButtonAction::ToggleMusic => volume.music_on = !volume.music_on,
+            SettingsButtonAction::ToggleAnimations => *speed = speed.next(),
+            SettingsButtonAction::ToggleTheme => theme.kind = theme.kind.next(),
+            SettingsButtonAction::ToggleColorblind => theme.colorblind = !theme.colorblind,
+            SettingsButtonAction::Back => app_state.set(AppState::InMenu),
+        }
+    }
+}
+
+/// Sets a volume to the point of its slider under the mouse for as long as
+/// the slider is held
+fn drag_sliders(
+    sliders: Query<(&Interaction, &RelativeCursorPosition, &VolumeSlider)>,
+    mut volume: ResMut<VolumeSettings>,
+) {
+    for (interaction, cursor, VolumeSlider(channel)) in &sliders {
+        let Some(position) = cursor
+            .normalized
+            .filter(|_| *interaction == Interaction::Pressed)
+        else {
+            continue;
+        };
+        // Only write when the value changes, the texts are refreshed on change
+        let mut dragged = *volume;
+        dragged.set(*channel, position.x);
+        if dragged != *volume {
+            *volume = dragged;
+        }
+    }
+}
+
+fn refresh_settings(
+    volume: Res<VolumeSettings>,
+    speed: Res<AnimationSpeed>,
+    theme: Res<Theme>,
+    mut texts: Query<(&mut Text, &SettingsText)>,
+    mut fills: Query<(&mut Style, &SliderFill)>,
+) {
+    for (mut text, label) in &mut texts {
+        text.sections[0].value = label.label(&volume, *speed, *theme);
+    }
+    for (mut style, SliderFill(channel)) in &mut fills {
+        style.width = Val::Percent(volume.get(*channel) * 100.0);
+    }
+}
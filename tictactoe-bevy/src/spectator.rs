@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    position::Position, spawn_symbol, theme::Theme, AppState, ButtonInteractions, GameData, KIMove,
+    Mode, OnGameScreen, Symbol, Tile, WinPossibilities,
+};
+
+pub struct SpectatorPlugin;
+
+/// Pause between two engine moves when a match starts
+const DEFAULT_DELAY: f32 = 0.5;
+/// Shortest and longest pause the faster and slower buttons allow
+const MIN_DELAY: f32 = 0.125;
+const MAX_DELAY: f32 = 4.0;
+
+/// A match between the two engines, played game after game until the
+/// spectator returns to the menu
+#[derive(Resource)]
+struct Spectator {
+    timer: Timer,
+    paused: bool,
+    /// Play one move, or start the rematch, while paused
+    step: bool,
+    /// Every game of the match starts from this position
+    start: Position,
+    start_moves: u32,
+    x_wins: u32,
+    o_wins: u32,
+    draws: u32,
+}
+
+impl Spectator {
+    fn status(&self) -> String {
+        format!(
+            "{} | Delay: {:.2}s | X {} - {} O, {} draws",
+            if self.paused { "Paused" } else { "Running" },
+            self.timer.duration().as_secs_f32(),
+            self.x_wins,
+            self.o_wins,
+            self.draws,
+        )
+    }
+
+    /// Multiplies the delay between moves by `factor`
+    fn scale_delay(&mut self, factor: f32) {
+        let delay = (self.timer.duration().as_secs_f32() * factor).clamp(MIN_DELAY, MAX_DELAY);
+        self.timer.set_duration(Duration::from_secs_f32(delay));
+    }
+}
+
+#[derive(Component)]
+struct SpectatorText;
+
+#[derive(Component)]
+enum SpectatorButtonAction {
+    PauseResume,
+    Step,
+    Slower,
+    Faster,
+    Menu,
+}
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            setup_spectator.run_if(|game_data: Option<Res<GameData>>| {
+                game_data.is_some_and(|data| data.mode == Mode::Spectate)
+            }),
+        )
+        .add_systems(
+            Update,
+            (
+                spectator_action,
+                count_result.run_if(resource_added::<WinPossibilities>),
+                play_turn,
+                update_spectator_text,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame))
+                .run_if(resource_exists::<Spectator>),
+        )
+        .add_systems(
+            OnExit(AppState::InGame),
+            // The match is dropped here rather than on Menu, the systems above
+            // still run on it for the rest of that frame
+            (|mut commands: Commands| {
+                commands.remove_resource::<Spectator>();
+                commands.remove_resource::<GameData>();
+                commands.remove_resource::<WinPossibilities>();
+            })
+            .run_if(resource_exists::<Spectator>),
+        );
+    }
+}
+
+fn setup_spectator(mut commands: Commands, game_data: Res<GameData>, theme: Res<Theme>) {
+    let palette = theme.palette();
+    let spectator = Spectator {
+        timer: Timer::from_seconds(DEFAULT_DELAY, TimerMode::Repeating),
+        paused: false,
+        step: false,
+        start: game_data.position.clone(),
+        start_moves: game_data.moves,
+        x_wins: 0,
+        o_wins: 0,
+        draws: 0,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(spectator.status(), palette.text_style()),
+                SpectatorText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(10.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (label, action) in [
+                        ("Pause / Resume", SpectatorButtonAction::PauseResume),
+                        ("Step", SpectatorButtonAction::Step),
+                        ("Slower", SpectatorButtonAction::Slower),
+                        ("Faster", SpectatorButtonAction::Faster),
+                        ("Menu", SpectatorButtonAction::Menu),
+                    ] {
+                        parent
+                            .spawn((palette.button_bundle(), action))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(label, palette.text_style()));
+                            });
+                    }
+                });
+        });
+    commands.insert_resource(spectator);
+}
+
+fn spectator_action(
+    interaction_query: ButtonInteractions<SpectatorButtonAction>,
+    mut spectator: ResMut<Spectator>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match action {
+                SpectatorButtonAction::PauseResume => spectator.paused = !spectator.paused,
+                SpectatorButtonAction::Step => {
+                    spectator.paused = true;
+                    spectator.step = true;
+                }
+                SpectatorButtonAction::Slower => spectator.scale_delay(2.0),
+                SpectatorButtonAction::Faster => spectator.scale_delay(0.5),
+                SpectatorButtonAction::Menu => app_state.set(AppState::InMenu),
+            }
+        }
+    }
+}
+
+fn count_result(win: Res<WinPossibilities>, mut spectator: ResMut<Spectator>) {
+    match *win {
+        WinPossibilities::XWon => spectator.x_wins += 1,
+        WinPossibilities::OWon => spectator.o_wins += 1,
+        WinPossibilities::Tie => spectator.draws += 1,
+        WinPossibilities::None => {}
+    }
+}
+
+/// Lets the engine to move play once the delay is over, or sets up the
+/// rematch once the game is decided
+#[allow(clippy::too_many_arguments)]
+fn play_turn(
+    time: Res<Time>,
+    mut spectator: ResMut<Spectator>,
+    mut game_data: ResMut<GameData>,
+    win: Option<Res<WinPossibilities>>,
+    tiles: Query<&Tile>,
+    symbols: Query<Entity, With<Symbol>>,
+    theme: Res<Theme>,
+    mut ki_moves: EventWriter<KIMove>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if spectator.paused {
+        if !std::mem::take(&mut spectator.step) {
+            return;
+        }
+    } else if !spectator.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if win.is_none() {
+        ki_moves.send(KIMove);
+        return;
+    }
+    commands.remove_resource::<WinPossibilities>();
+    for entity in &symbols {
+        commands.entity(entity).despawn();
+    }
+    game_data.position = spectator.start.clone();
+    game_data.moves = spectator.start_moves;
+    game_data.won = false;
+    game_data.history.clear();
+    game_data.undone.clear();
+    for tile in &tiles {
+        if let Some(symbol) = game_data.symbol_at(tile.index) {
+            spawn_symbol(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &theme,
+                tile,
+                symbol,
+                OnGameScreen,
+            );
+        }
+    }
+}
+
+fn update_spectator_text(
+    spectator: Res<Spectator>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = spectator.status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::AssetPlugin, input::InputPlugin, state::app::StatesPlugin, window::WindowResized,
+    };
+    use tictactoe_logic::grid::Grid;
+
+    use super::*;
+    use crate::{
+        animation::AnimationPlugin, theme::ThemePlugin, GamePlugin, PlayerChoice, Variant,
+    };
+
+    #[test]
+    fn menu_leaves_a_running_match() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            InputPlugin,
+            StatesPlugin,
+            GamePlugin,
+            AnimationPlugin,
+            ThemePlugin,
+            SpectatorPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .add_event::<WindowResized>()
+        .insert_resource(GameData::new(
+            Grid::new(3, 3),
+            PlayerChoice::X,
+            Variant::Classic,
+            Mode::Spectate,
+            Default::default(),
+        ));
+        app.world_mut().spawn(Window::default());
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+        assert!(app.world().contains_resource::<Spectator>());
+
+        let mut buttons = app
+            .world_mut()
+            .query::<(&mut Interaction, &SpectatorButtonAction)>();
+        for (mut interaction, action) in buttons.iter_mut(app.world_mut()) {
+            if matches!(action, SpectatorButtonAction::Menu) {
+                *interaction = Interaction::Pressed;
+            }
+        }
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::InMenu
+        );
+        assert!(!app.world().contains_resource::<GameData>());
+        assert!(!app.world().contains_resource::<Spectator>());
+    }
+}
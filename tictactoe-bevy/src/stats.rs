@@ -0,0 +1,599 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::write_ron,
+    despawn_screen,
+    menu::{MAX_SIZE, MIN_SIZE},
+    replay::Replay,
+    scoreboard::next_game,
+    theme::{spawn_button, Theme, ThemeColor},
+    AppState, ButtonAction, ButtonInteractions, Engine, EngineSettings, GameData, Mode,
+    PlayerChoice, Variant, WinPossibilities,
+};
+
+pub struct StatsPlugin;
+
+/// Version of the statistics file written by this build
+const STATS_VERSION: u32 = 1;
+
+/// Number of games listed on the statistics screen
+const RECENT_GAMES: usize = 8;
+
+/// Result of a game for the human
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win => write!(f, "Win"),
+            Outcome::Loss => write!(f, "Loss"),
+            Outcome::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+/// A finished game against the computer, with every move for the replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Seconds since the Unix epoch when the game ended
+    pub date: u64,
+    pub player: PlayerChoice,
+    pub variant: Variant,
+    pub rows: usize,
+    pub cols: usize,
+    pub win_length: usize,
+    /// Engine the human played against
+    pub engine: EngineSettings,
+    pub outcome: Outcome,
+    /// Symbols on the board before the first move, row by row. Games from
+    /// the board editor don't start out empty.
+    pub start: Vec<Option<PlayerChoice>>,
+    /// Field and symbol of every move, oldest first
+    pub moves: Vec<(usize, PlayerChoice)>,
+}
+
+impl GameRecord {
+    /// Record of a decided game, `None` unless it was played against the
+    /// computer
+    fn new(game_data: &GameData, win: &WinPossibilities, date: u64) -> Option<Self> {
+        if game_data.mode != Mode::Computer {
+            return None;
+        }
+        let outcome = match (win, game_data.player) {
+            (WinPossibilities::None, _) => return None,
+            (WinPossibilities::Tie, _) => Outcome::Draw,
+            (WinPossibilities::XWon, PlayerChoice::X)
+            | (WinPossibilities::OWon, PlayerChoice::O) => Outcome::Win,
+            _ => Outcome::Loss,
+        };
+        let (rows, cols) = game_data.grid().size();
+        let moves: Vec<(usize, PlayerChoice)> = game_data
+            .history
+            .iter()
+            .filter_map(|placement| {
+                PlayerChoice::from_field_states(placement.value)
+                    .map(|symbol| (placement.index, symbol))
+            })
+            .collect();
+        let start = (0..rows * cols)
+            .map(|index| {
+                game_data
+                    .symbol_at(index as u32)
+                    .filter(|_| moves.iter().all(|(played, _)| *played != index))
+            })
+            .collect();
+        Some(Self {
+            date,
+            player: game_data.player,
+            variant: game_data.variant,
+            rows,
+            cols,
+            win_length: game_data.grid().win_length(),
+            engine: game_data.engine(game_data.player.opposite()),
+            outcome,
+            start,
+            moves,
+        })
+    }
+
+    /// Whether the board fits the menu's limits and the start position and
+    /// moves fit the board, so the record can be replayed
+    fn fits_board(&self) -> bool {
+        let size = self.rows * self.cols;
+        (MIN_SIZE..=MAX_SIZE).contains(&self.rows)
+            && (MIN_SIZE..=MAX_SIZE).contains(&self.cols)
+            && (MIN_SIZE..=self.rows.min(self.cols)).contains(&self.win_length)
+            && self.start.len() == size
+            && self.moves.iter().all(|(index, _)| *index < size)
+    }
+
+    fn difficulty(&self) -> String {
+        match self.engine.engine {
+            Engine::MiniMax => format!("MiniMax, depth {}", self.engine.depth),
+            Engine::Random => "Random".to_string(),
+        }
+    }
+
+    fn board(&self) -> String {
+        format!("{}x{}, {} in a row", self.rows, self.cols, self.win_length)
+    }
+
+    /// One line of the recent games list
+    fn summary(&self) -> String {
+        format!(
+            "{}  {} as {:?}, {} {} vs {}",
+            format_date(self.date),
+            self.outcome,
+            self.player,
+            self.board(),
+            self.variant,
+            self.difficulty()
+        )
+    }
+}
+
+/// Date and time in UTC, as "YYYY-MM-DD HH:MM"
+fn format_date(seconds: u64) -> String {
+    // Days to civil dates after Howard Hinnant's `civil_from_days`
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let minutes = seconds % 86_400 / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Games played, won, lost and drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Tally {
+    played: u32,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+}
+
+impl Tally {
+    fn add(&mut self, outcome: Outcome) {
+        self.played += 1;
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Tally {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} played, {} won, {} lost, {} drawn",
+            self.played, self.wins, self.losses, self.draws
+        )
+    }
+}
+
+/// Every game ever finished against the computer, oldest first
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Statistics {
+    games: Vec<GameRecord>,
+}
+
+/// [`Statistics`] as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+struct StatsFile {
+    version: u32,
+    games: Vec<GameRecord>,
+}
+
+impl Statistics {
+    fn total(&self) -> Tally {
+        let mut tally = Tally::default();
+        for game in &self.games {
+            tally.add(game.outcome);
+        }
+        tally
+    }
+
+    /// Tallies of the games grouped by `key`, in the order the groups were
+    /// first played
+    fn tally_by(&self, key: impl Fn(&GameRecord) -> String) -> Vec<(String, Tally)> {
+        let mut groups: Vec<(String, Tally)> = Vec::new();
+        for game in &self.games {
+            let key = key(game);
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, tally)) => tally.add(game.outcome),
+                None => {
+                    let mut tally = Tally::default();
+                    tally.add(game.outcome);
+                    groups.push((key, tally));
+                }
+            }
+        }
+        groups
+    }
+
+    /// Wins in a row up to the last game, and the most ever
+    fn streaks(&self) -> (u32, u32) {
+        let (mut current, mut best) = (0, 0);
+        for game in &self.games {
+            if game.outcome == Outcome::Win {
+                current += 1;
+                best = best.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        (current, best)
+    }
+
+    /// Moves per game, `None` before the first game
+    fn average_length(&self) -> Option<f32> {
+        let moves: usize = self.games.iter().map(|game| game.moves.len()).sum();
+        (!self.games.is_empty()).then(|| moves as f32 / self.games.len() as f32)
+    }
+
+    /// Indices of the latest games, newest first
+    fn recent(&self) -> impl Iterator<Item = usize> {
+        (0..self.games.len()).rev().take(RECENT_GAMES)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let file = ron::from_str::<StatsFile>(text)
+            .map_err(|error| warn!("Ignoring corrupt statistics file: {}", error))
+            .ok()?;
+        if file.version != STATS_VERSION {
+            warn!(
+                "Ignoring statistics file of unknown version {}",
+                file.version
+            );
+            return None;
+        }
+        let count = file.games.len();
+        let games: Vec<GameRecord> = file
+            .games
+            .into_iter()
+            .filter(GameRecord::fits_board)
+            .collect();
+        if games.len() < count {
+            warn!(
+                "Dropping {} game(s) that don't fit their board from the statistics",
+                count - games.len()
+            );
+        }
+        Some(Self { games })
+    }
+
+    fn save(&self) {
+        let Some(path) = stats_path() else {
+            return;
+        };
+        let file = StatsFile {
+            version: STATS_VERSION,
+            games: self.games.clone(),
+        };
+        if let Err(error) = write_ron(&path, &file) {
+            warn!(
+                "Couldn't save the statistics to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Statistics file in the platform's data directory, next to the saved
+/// games
+fn stats_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("tictactoe").join("stats.ron"))
+}
+
+#[derive(Component)]
+struct OnStatsScreen;
+
+#[derive(Component)]
+enum StatsButtonAction {
+    /// Opens the game with the given index in the replay viewer
+    Replay(usize),
+    Back,
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let stats = stats_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| Statistics::parse(&text))
+            .unwrap_or_default();
+        app.insert_resource(stats)
+            .add_systems(
+                Update,
+                record_game
+                    // Runs before the board is cleared for the next game
+                    .before(next_game)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_exists::<WinPossibilities>)
+                    .run_if(resource_exists::<GameData>),
+            )
+            .add_systems(Last, record_game_on_exit.run_if(on_event::<AppExit>()))
+            .add_systems(OnEnter(AppState::InStats), setup_stats)
+            .add_systems(Update, stats_action.run_if(in_state(AppState::InStats)))
+            .add_systems(OnExit(AppState::InStats), despawn_screen::<OnStatsScreen>);
+    }
+}
+
+fn record(stats: &mut Statistics, game_data: &GameData, win: &WinPossibilities) {
+    let date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    if let Some(game) = GameRecord::new(game_data, win, date) {
+        stats.games.push(game);
+        stats.save();
+    }
+}
+
+/// Counts a decided game once the player leaves its result, so a result taken
+/// back with undo never counts
+fn record_game(
+    interaction_query: ButtonInteractions<ButtonAction>,
+    game_data: Res<GameData>,
+    win: Res<WinPossibilities>,
+    mut stats: ResMut<Statistics>,
+) {
+    if interaction_query
+        .iter()
+        .any(|(interaction, _)| *interaction == Interaction::Pressed)
+    {
+        record(&mut stats, &game_data, &win);
+    }
+}
+
+/// Counts a decided game when the window is closed on its result
+fn record_game_on_exit(
+    game_data: Option<Res<GameData>>,
+    win: Option<Res<WinPossibilities>>,
+    mut stats: ResMut<Statistics>,
+) {
+    if let Some((game_data, win)) = game_data.zip(win) {
+        record(&mut stats, &game_data, &win);
+    }
+}
+
+fn setup_stats(mut commands: Commands, stats: Res<Statistics>, theme: Res<Theme>) {
+    let palette = theme.palette();
+    let mut lines = vec![format!("Total: {}", stats.total())];
+    if let Some(average) = stats.average_length() {
+        let (current, best) = stats.streaks();
+        lines.push(format!("Win streak: {} now, {} at best", current, best));
+        lines.push(format!("Average game: {:.1} moves", average));
+    } else {
+        lines.push("No games against the computer yet".to_string());
+    }
+    for (title, groups) in [
+        ("By difficulty", stats.tally_by(GameRecord::difficulty)),
+        ("By board", stats.tally_by(GameRecord::board)),
+        (
+            "By variant",
+            stats.tally_by(|game| game.variant.to_string()),
+        ),
+    ] {
+        if groups.is_empty() {
+            continue;
+        }
+        lines.push(String::new());
+        lines.push(title.to_string());
+        for (group, tally) in groups {
+            lines.push(format!("  {}: {}", group, tally));
+        }
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnStatsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        border_radius: BorderRadius::all(Val::Percent(5.0)),
+                        background_color: BackgroundColor(palette.panel),
+                        border_color: BorderColor(palette.panel_border),
+                        style: Style {
+                            border: UiRect::all(Val::Px(1.0)),
+                            padding: UiRect::all(Val::Px(20.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(10.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    ThemeColor::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        lines.join("\n"),
+                        palette.text_style(),
+                    ));
+                    for index in stats.recent() {
+                        spawn_button(parent, palette, StatsButtonAction::Replay(index))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    stats.games[index].summary(),
+                                    palette.text_style(),
+                                ));
+                            });
+                    }
+                    spawn_button(parent, palette, StatsButtonAction::Back).with_children(
+                        |parent| {
+                            parent.spawn(TextBundle::from_section("Back", palette.text_style()));
+                        },
+                    );
+                });
+        });
+}
+
+fn stats_action(
+    interaction_query: ButtonInteractions<StatsButtonAction>,
+    stats: Res<Statistics>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            StatsButtonAction::Replay(index) => {
+                if let Some(game) = stats.games.get(*index) {
+                    commands.insert_resource(Replay::new(game.clone()));
+                    app_state.set(AppState::InReplay);
+                }
+            }
+            StatsButtonAction::Back => app_state.set(AppState::InMenu),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(outcome: Outcome, moves: usize, depth: i8) -> GameRecord {
+        GameRecord {
+            date: 0,
+            player: PlayerChoice::X,
+            variant: Variant::Classic,
+            rows: 3,
+            cols: 3,
+            win_length: 3,
+            engine: EngineSettings {
+                engine: Engine::MiniMax,
+                depth,
+            },
+            outcome,
+            start: vec![None; 9],
+            moves: vec![(0, PlayerChoice::X); moves],
+        }
+    }
+
+    #[test]
+    fn records_keep_the_start_position_apart_from_the_moves() {
+        // Set up in the editor
+        let mut game_data = GameData::new(
+            "0 0 0\n0 1 0\n0 0 0".parse().unwrap(),
+            PlayerChoice::O,
+            Variant::Classic,
+            Mode::Computer,
+            Default::default(),
+        );
+        game_data.make_move(0).unwrap();
+
+        let record = GameRecord::new(&game_data, &WinPossibilities::XWon, 0).unwrap();
+        assert_eq!(record.outcome, Outcome::Loss);
+        assert_eq!(record.start[4], Some(PlayerChoice::X));
+        assert_eq!(record.start[0], None);
+        assert_eq!(record.moves, vec![(0, PlayerChoice::O)]);
+        assert_eq!(record.engine, game_data.engine(PlayerChoice::X));
+
+        game_data.mode = Mode::Hotseat;
+        assert!(GameRecord::new(&game_data, &WinPossibilities::XWon, 0).is_none());
+    }
+
+    #[test]
+    fn statistics_tally_streaks_and_lengths() {
+        let stats = Statistics {
+            games: vec![
+                finished(Outcome::Win, 5, 9),
+                finished(Outcome::Win, 7, 9),
+                finished(Outcome::Draw, 9, 3),
+                finished(Outcome::Win, 7, 3),
+            ],
+        };
+        assert_eq!(stats.streaks(), (1, 2));
+        assert_eq!(stats.average_length(), Some(7.0));
+        let by_difficulty = stats.tally_by(GameRecord::difficulty);
+        assert_eq!(by_difficulty.len(), 2);
+        assert_eq!(by_difficulty[1].0, "MiniMax, depth 3");
+        assert_eq!(by_difficulty[1].1.draws, 1);
+        assert_eq!(stats.recent().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+        assert_eq!(Statistics::default().average_length(), None);
+    }
+
+    #[test]
+    fn records_that_dont_fit_their_board_are_dropped() {
+        let too_large = GameRecord {
+            rows: MAX_SIZE + 1,
+            start: vec![None; (MAX_SIZE + 1) * 3],
+            ..finished(Outcome::Win, 5, 9)
+        };
+        let long_win = GameRecord {
+            win_length: 4,
+            ..finished(Outcome::Win, 5, 9)
+        };
+        let short_start = GameRecord {
+            start: vec![None; 8],
+            ..finished(Outcome::Win, 5, 9)
+        };
+        let mut off_board = finished(Outcome::Loss, 5, 9);
+        off_board.moves[2].0 = 9;
+        let file = StatsFile {
+            version: STATS_VERSION,
+            games: vec![
+                too_large,
+                finished(Outcome::Draw, 9, 3),
+                long_win,
+                short_start,
+                off_board,
+            ],
+        };
+        let stats = Statistics::parse(&ron::to_string(&file).unwrap()).unwrap();
+        assert_eq!(stats.games.len(), 1);
+        assert_eq!(stats.games[0].outcome, Outcome::Draw);
+    }
+
+    #[test]
+    fn dates_are_formatted_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01 00:00");
+        assert_eq!(format_date(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14 22:13");
+    }
+}
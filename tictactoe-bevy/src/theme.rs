@@ -0,0 +1,381 @@
+use bevy::{
+    color::palettes::{
+        css::{BLACK, RED, WHITE},
+        tailwind::{
+            AMBER_400, AMBER_500, BLUE_100, BLUE_200, BLUE_300, BLUE_400, BLUE_700, BLUE_800,
+            BLUE_900, GRAY_100, GRAY_200, GRAY_300, GRAY_50, GRAY_500, GRAY_700, GRAY_800,
+            GRAY_900, RED_300,
+        },
+    },
+    ecs::system::EntityCommands,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{layout::HoveredTile, PlayerChoice, Symbol, Tile};
+
+pub struct ThemePlugin;
+
+/// Colour from 8 bit channels, usable in constants
+const fn rgb(red: u8, green: u8, blue: u8) -> Color {
+    Color::srgb(
+        red as f32 / 255.0,
+        green as f32 / 255.0,
+        blue as f32 / 255.0,
+    )
+}
+
+/// Every colour of the user interface and the board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    /// Shows between the tiles as grid lines
+    pub grid: Color,
+    pub tile: Color,
+    pub tile_hovered: Color,
+    pub symbol: Color,
+    pub text: Color,
+    pub button: Color,
+    pub button_hovered: Color,
+    pub button_pressed: Color,
+    pub button_border: Color,
+    /// Buttons that leave the game
+    pub danger: Color,
+    /// Box holding the buttons of the menu and settings screens
+    pub panel: Color,
+    pub panel_border: Color,
+    /// Keyboard cursor, button focus, hints and the winning line
+    pub highlight: Color,
+    /// Empty part of the volume sliders
+    pub track: Color,
+    pub good: Color,
+    pub neutral: Color,
+    pub bad: Color,
+}
+
+impl Palette {
+    pub fn text_style(&self) -> TextStyle {
+        TextStyle {
+            color: self.text,
+            ..Default::default()
+        }
+    }
+
+    /// Border and padding every button shares, screens add their own layout
+    /// on top
+    pub fn button_style() -> Style {
+        Style {
+            border: UiRect::all(Val::Px(1.0)),
+            padding: UiRect::all(Val::Px(20.0))
+                .with_top(Val::Px(5.0))
+                .with_bottom(Val::Px(5.0)),
+            ..Default::default()
+        }
+    }
+
+    pub fn button_bundle(&self) -> ButtonBundle {
+        ButtonBundle {
+            background_color: BackgroundColor(self.button),
+            border_radius: BorderRadius::all(Val::Percent(20.0)),
+            border_color: BorderColor(self.button_border),
+            style: Self::button_style(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Spawns a themed button carrying `action`, its label is added by the caller
+pub fn spawn_button<'a>(
+    parent: &'a mut ChildBuilder,
+    palette: &Palette,
+    action: impl Bundle,
+) -> EntityCommands<'a> {
+    parent.spawn((palette.button_bundle(), action))
+}
+
+const DARK: Palette = Palette {
+    background: rgb(43, 44, 47),
+    grid: Color::Srgba(WHITE),
+    tile: rgb(43, 44, 47),
+    tile_hovered: rgb(70, 72, 77),
+    symbol: Color::Srgba(GRAY_50),
+    text: Color::Srgba(WHITE),
+    button: Color::Srgba(BLUE_400),
+    button_hovered: Color::Srgba(BLUE_800),
+    button_pressed: Color::Srgba(BLUE_900),
+    button_border: Color::Srgba(BLACK),
+    danger: Color::Srgba(RED),
+    panel: Color::Srgba(BLUE_700),
+    panel_border: Color::Srgba(BLUE_800),
+    highlight: Color::Srgba(AMBER_400),
+    track: Color::Srgba(GRAY_700),
+    good: rgb(0x3C, 0xB0, 0x4A),
+    neutral: rgb(0x90, 0x90, 0x90),
+    bad: rgb(0xE0, 0x4A, 0x4A),
+};
+
+const LIGHT: Palette = Palette {
+    background: Color::Srgba(GRAY_100),
+    grid: Color::Srgba(GRAY_800),
+    tile: Color::Srgba(GRAY_50),
+    tile_hovered: Color::Srgba(GRAY_200),
+    symbol: Color::Srgba(GRAY_900),
+    text: Color::Srgba(GRAY_900),
+    button: Color::Srgba(BLUE_200),
+    button_hovered: Color::Srgba(BLUE_300),
+    button_pressed: Color::Srgba(BLUE_400),
+    button_border: Color::Srgba(GRAY_500),
+    danger: Color::Srgba(RED_300),
+    panel: Color::Srgba(BLUE_100),
+    panel_border: Color::Srgba(BLUE_300),
+    highlight: Color::Srgba(AMBER_500),
+    track: Color::Srgba(GRAY_300),
+    good: rgb(0x2E, 0x9A, 0x3C),
+    neutral: rgb(0x80, 0x80, 0x80),
+    bad: rgb(0xD0, 0x3A, 0x3A),
+};
+
+/// Pure black and white with a yellow highlight
+const HIGH_CONTRAST: Palette = Palette {
+    background: Color::Srgba(BLACK),
+    grid: Color::Srgba(WHITE),
+    tile: Color::Srgba(BLACK),
+    tile_hovered: rgb(64, 64, 64),
+    symbol: Color::Srgba(WHITE),
+    text: Color::Srgba(WHITE),
+    button: Color::Srgba(BLACK),
+    button_hovered: rgb(64, 64, 64),
+    button_pressed: rgb(110, 110, 110),
+    button_border: Color::Srgba(WHITE),
+    danger: rgb(170, 0, 0),
+    panel: Color::Srgba(BLACK),
+    panel_border: Color::Srgba(WHITE),
+    highlight: rgb(255, 255, 0),
+    track: rgb(64, 64, 64),
+    good: rgb(0, 220, 0),
+    neutral: rgb(160, 160, 160),
+    bad: rgb(255, 60, 60),
+};
+
+/// Blue and orange of the Okabe-Ito palette, which stay apart with any kind
+/// of colour blindness
+const COLORBLIND_BLUE: Color = rgb(0, 114, 178);
+const COLORBLIND_ORANGE: Color = rgb(230, 159, 0);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemeKind {
+    pub fn next(&self) -> Self {
+        match self {
+            ThemeKind::Dark => ThemeKind::Light,
+            ThemeKind::Light => ThemeKind::HighContrast,
+            ThemeKind::HighContrast => ThemeKind::Dark,
+        }
+    }
+}
+
+impl std::fmt::Display for ThemeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeKind::Dark => write!(f, "Dark"),
+            ThemeKind::Light => write!(f, "Light"),
+            ThemeKind::HighContrast => write!(f, "High contrast"),
+        }
+    }
+}
+
+/// Look of the game, switched in the settings. Changing it recolours
+/// everything on screen.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    /// Draw X in blue and O in orange, and tint hints blue and orange instead
+    /// of green and red
+    pub colorblind: bool,
+}
+
+impl Theme {
+    pub fn palette(&self) -> &'static Palette {
+        match self.kind {
+            ThemeKind::Dark => &DARK,
+            ThemeKind::Light => &LIGHT,
+            ThemeKind::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+
+    pub fn tile(&self, hovered: bool) -> Color {
+        if hovered {
+            self.palette().tile_hovered
+        } else {
+            self.palette().tile
+        }
+    }
+
+    pub fn symbol(&self, side: PlayerChoice) -> Color {
+        match (self.colorblind, side) {
+            (false, _) => self.palette().symbol,
+            (true, PlayerChoice::X) => COLORBLIND_BLUE,
+            (true, PlayerChoice::O) => COLORBLIND_ORANGE,
+        }
+    }
+
+    /// Tint of good, neutral and bad outcomes
+    pub fn outcome(&self, good: Option<bool>) -> Color {
+        match (self.colorblind, good) {
+            (_, None) => self.palette().neutral,
+            (true, Some(true)) => COLORBLIND_BLUE,
+            (true, Some(false)) => COLORBLIND_ORANGE,
+            (false, Some(true)) => self.palette().good,
+            (false, Some(false)) => self.palette().bad,
+        }
+    }
+
+    /// Background of a button in the given state
+    pub fn button(&self, interaction: Interaction) -> Color {
+        match interaction {
+            Interaction::Pressed => self.palette().button_pressed,
+            Interaction::Hovered => self.palette().button_hovered,
+            Interaction::None => self.palette().button,
+        }
+    }
+}
+
+/// Part of the screen whose colour doesn't follow from its other components
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    Grid,
+    Panel,
+    Danger,
+    Highlight,
+    Track,
+    /// Filled part of a slider, coloured like a button
+    Fill,
+}
+
+impl ThemeColor {
+    fn color(self, palette: &Palette) -> Color {
+        match self {
+            ThemeColor::Grid => palette.grid,
+            ThemeColor::Panel => palette.panel,
+            ThemeColor::Danger => palette.danger,
+            ThemeColor::Highlight => palette.highlight,
+            ThemeColor::Track => palette.track,
+            ThemeColor::Fill => palette.button,
+        }
+    }
+}
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .insert_resource(ClearColor(Theme::default().palette().background))
+            .add_systems(Update, apply_theme.run_if(resource_changed::<Theme>));
+    }
+}
+
+/// Recolours everything on screen in the current theme. Alpha is kept, so
+/// overlays that fade stay as transparent as they were.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn apply_theme(
+    theme: Res<Theme>,
+    hovered: Option<Res<HoveredTile>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, &mut BorderColor),
+        (With<Button>, Without<ThemeColor>),
+    >,
+    mut nodes: Query<
+        (
+            &ThemeColor,
+            Option<&mut BackgroundColor>,
+            Option<&mut BorderColor>,
+            Option<&Handle<ColorMaterial>>,
+        ),
+        Without<Button>,
+    >,
+    mut danger_buttons: Query<
+        (&mut BackgroundColor, &mut BorderColor),
+        (With<Button>, With<ThemeColor>),
+    >,
+    mut texts: Query<&mut Text>,
+    tiles: Query<(&Tile, &Handle<ColorMaterial>)>,
+    symbols: Query<(&Symbol, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let palette = theme.palette();
+    clear_color.0 = palette.background;
+    let mut recolor = |handle: &Handle<ColorMaterial>, color: Color| {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color.with_alpha(material.color.alpha());
+        }
+    };
+
+    for (interaction, mut background, mut border) in &mut buttons {
+        background.0 = theme.button(*interaction);
+        border.0 = palette.button_border;
+    }
+    for (mut background, mut border) in &mut danger_buttons {
+        background.0 = palette.danger;
+        border.0 = palette.button_border;
+    }
+    for (role, background, border, material) in &mut nodes {
+        let color = role.color(palette);
+        if let Some(mut background) = background {
+            background.0 = color.with_alpha(background.0.alpha());
+        }
+        if let Some(mut border) = border {
+            border.0 = palette.panel_border;
+        }
+        if let Some(material) = material {
+            recolor(material, color);
+        }
+    }
+    for mut text in &mut texts {
+        for section in &mut text.sections {
+            section.style.color = palette.text.with_alpha(section.style.color.alpha());
+        }
+    }
+    let hovered = hovered.and_then(|hovered| hovered.0);
+    for (tile, material) in &tiles {
+        recolor(material, theme.tile(hovered == Some(tile.index)));
+    }
+    for (symbol, material) in &symbols {
+        let side = if symbol.is_x {
+            PlayerChoice::X
+        } else {
+            PlayerChoice::O
+        };
+        recolor(material, theme.symbol(side));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorblind_symbols_tell_the_sides_apart() {
+        for kind in [ThemeKind::Dark, ThemeKind::Light, ThemeKind::HighContrast] {
+            let theme = Theme {
+                kind,
+                colorblind: false,
+            };
+            assert_eq!(theme.symbol(PlayerChoice::X), theme.symbol(PlayerChoice::O));
+
+            let theme = Theme {
+                kind,
+                colorblind: true,
+            };
+            assert_ne!(theme.symbol(PlayerChoice::X), theme.symbol(PlayerChoice::O));
+            assert_ne!(theme.outcome(Some(true)), theme.outcome(Some(false)));
+            assert_ne!(theme.symbol(PlayerChoice::X), theme.tile(false));
+        }
+    }
+}
@@ -1,60 +1,797 @@
-use iced::futures;
-use iced::widget::{self, center, column, row, text};
-use iced::*;
-use iced::{Center, Element, Fill, Right, Task};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use iced::futures::SinkExt;
+use iced::task;
+use iced::widget::{button, canvas, center, column, row, text, text_editor};
+use iced::{Center, Element, Fill, Task};
+use tictactoe_logic::grid::{FieldStates, Grid};
+use tictactoe_logic::minimax::{Analysis, MiniMax};
+use tictactoe_logic::rules::{empty_fields, Classic, Placement, Rules, Status};
+
 pub fn main() -> iced::Result {
     iced::application("TicTacToe Rust", TicTacToeApp::update, TicTacToeApp::view)
         .run_with(TicTacToeApp::new)
 }
 
+/// The human plays X, the AI answers with O
+const HUMAN: FieldStates = FieldStates::Player1;
+const AI: FieldStates = FieldStates::Player2;
+
 #[derive(Debug)]
 enum TicTacToe {
     Player1,
     Player2,
     Stopped,
+    /// Setting up a position in the board editor
+    Editing,
 }
 
 pub struct TicTacToeApp {
     grid: Grid,
     state: TicTacToe,
+    /// Bumped on every new game so answers from an AI search started in an
+    /// earlier game are dropped
+    game: u64,
+    /// Latest engine evaluation of the current position, deepened while the
+    /// position stays the same
+    analysis: Option<Analysis<Placement>>,
+    /// Bumped whenever the position changes, evaluations of older positions
+    /// are dropped
+    position: u64,
+    /// Stops the running analysis once it is replaced
+    analysis_handle: Option<task::Handle>,
+    /// Set once the running analysis is replaced. The searches never yield,
+    /// so the abort through the handle may go unnoticed for several depths,
+    /// the flag is checked before each one.
+    analysis_cancelled: Arc<AtomicBool>,
+    /// Side to move in the position set up in the editor
+    to_move: FieldStates,
+    /// Position as text, for importing and exporting it in the editor
+    position_text: text_editor::Content,
+    /// Why the edited position can't be played, or why the import failed
+    setup_error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     PlayerMoved((i64, i64, FieldStates)),
+    AiMoved(u64, Option<Placement>),
+    NewGame,
+    Analysed(u64, Analysis<Placement>),
+    Edit,
+    CellEdited((i64, i64)),
+    SideToMove(FieldStates),
+    PositionTextEdited(text_editor::Action),
+    Import,
+    Export,
+    StartGame,
 }
 
 impl TicTacToeApp {
     fn new() -> (Self, Task<Message>) {
-        (
-            Self {
-                grid: Grid::new(3, 3),
-                state: TicTacToe::Stopped,
-            },
-            Task::none(),
-        )
+        let mut app = Self {
+            grid: Grid::new(3, 3),
+            state: TicTacToe::Player1,
+            game: 0,
+            analysis: None,
+            position: 0,
+            analysis_handle: None,
+            analysis_cancelled: Arc::new(AtomicBool::new(false)),
+            to_move: HUMAN,
+            position_text: text_editor::Content::new(),
+            setup_error: None,
+        };
+        let analysis = app.analyse();
+        (app, analysis)
     }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            _ => Task::none(),
+            Message::PlayerMoved((row, col, player)) => {
+                if !matches!(self.state, TicTacToe::Player1) || player != HUMAN {
+                    return Task::none();
+                }
+                if self.grid.set(row as usize, col as usize, player).is_none() {
+                    return Task::none();
+                }
+                let ai_move = self.advance();
+                Task::batch([ai_move, self.analyse()])
+            }
+            Message::AiMoved(game, placement) => {
+                if game != self.game || !matches!(self.state, TicTacToe::Player2) {
+                    return Task::none();
+                }
+                if let Some(placement) = placement {
+                    self.grid.set_elem(placement.index, placement.value);
+                }
+                let ai_move = self.advance();
+                Task::batch([ai_move, self.analyse()])
+            }
+            Message::NewGame => {
+                let (rows, cols) = self.grid.size();
+                self.grid = Grid::new(rows, cols);
+                self.state = TicTacToe::Player1;
+                self.setup_error = None;
+                self.game += 1;
+                self.analyse()
+            }
+            Message::Analysed(position, analysis) => {
+                if position == self.position {
+                    self.analysis = Some(analysis);
+                }
+                Task::none()
+            }
+            Message::Edit => {
+                // Stops any AI search still running for the old game
+                self.game += 1;
+                self.state = TicTacToe::Editing;
+                self.to_move = self.expected_to_move();
+                self.position_text = text_editor::Content::with_text(&self.grid.to_string());
+                self.validate();
+                self.analyse()
+            }
+            Message::CellEdited((row, col)) => {
+                let (_, cols) = self.grid.size();
+                let index = row as usize * cols + col as usize;
+                let next = match self.grid.clear_elem(index) {
+                    Some(FieldStates::Empty) => FieldStates::Player1,
+                    Some(FieldStates::Player1) => FieldStates::Player2,
+                    _ => FieldStates::Empty,
+                };
+                self.grid.set_elem(index, next);
+                self.to_move = self.expected_to_move();
+                self.validate();
+                self.analyse()
+            }
+            Message::SideToMove(to_move) => {
+                self.to_move = to_move;
+                self.validate();
+                self.analyse()
+            }
+            Message::PositionTextEdited(action) => {
+                self.position_text.perform(action);
+                Task::none()
+            }
+            Message::Import => match Grid::from_str(&self.position_text.text()) {
+                Ok(grid) => {
+                    self.grid = grid;
+                    self.to_move = self.expected_to_move();
+                    self.validate();
+                    self.analyse()
+                }
+                Err(error) => {
+                    self.setup_error = Some(error);
+                    Task::none()
+                }
+            },
+            Message::Export => {
+                self.position_text = text_editor::Content::with_text(&self.grid.to_string());
+                Task::none()
+            }
+            Message::StartGame => {
+                if !self.validate() {
+                    return Task::none();
+                }
+                self.game += 1;
+                if self.status() != Status::Ongoing {
+                    self.state = TicTacToe::Stopped;
+                    return self.analyse();
+                }
+                let ai_move = if self.to_move == HUMAN {
+                    self.state = TicTacToe::Player1;
+                    Task::none()
+                } else {
+                    self.state = TicTacToe::Player2;
+                    self.ai_move()
+                };
+                Task::batch([ai_move, self.analyse()])
+            }
         }
     }
 
-    fn view(&self) -> Element<Message> {
-        let content: Element<_> = match self.state {
-            TicTacToe::Player1 => text("Player1").into(),
-            TicTacToe::Player2 => column![text("Player2")].into(),
-            TicTacToe::Stopped => text("Stopped").into(),
+    /// Side whose turn it is by the number of symbols on the board, X when
+    /// both have placed as many
+    fn expected_to_move(&self) -> FieldStates {
+        let count = |state: FieldStates| {
+            self.grid
+                .clone()
+                .into_iter()
+                .filter(|field| *field == state)
+                .count()
+        };
+        if count(FieldStates::Player1) > count(FieldStates::Player2) {
+            FieldStates::Player2
+        } else {
+            FieldStates::Player1
+        }
+    }
+
+    /// Checks the edited position, remembering why it can't be played
+    fn validate(&mut self) -> bool {
+        self.setup_error = Classic::setup(self.grid.clone(), self.to_move).err();
+        self.setup_error.is_none()
+    }
+
+    /// Cancels the evaluation of the previous position and starts analysing
+    /// the current one, one search depth deeper with every update
+    fn analyse(&mut self) -> Task<Message> {
+        self.position += 1;
+        self.analysis = None;
+        // Dropping the handle aborts the old search
+        self.analysis_handle = None;
+        self.analysis_cancelled.store(true, Ordering::Relaxed);
+        self.analysis_cancelled = Arc::new(AtomicBool::new(false));
+        if self.status() != Status::Ongoing || self.setup_error.is_some() {
+            return Task::none();
+        }
+
+        let to_move = match self.state {
+            TicTacToe::Player2 => AI,
+            TicTacToe::Editing => self.to_move,
+            _ => HUMAN,
         };
-        for field in self.grid {}
+        let root = Classic::new(self.grid.clone(), to_move);
+        let max_depth = empty_fields(&self.grid).len() as i8;
+        let position = self.position;
+        let cancelled = self.analysis_cancelled.clone();
+        let (analysis, handle) = Task::run(
+            iced::stream::channel(1, move |mut output| async move {
+                for analysis in deepen(root, max_depth, cancelled) {
+                    if output.send(analysis).await.is_err() {
+                        break;
+                    }
+                }
+            }),
+            move |analysis| Message::Analysed(position, analysis),
+        )
+        .abortable();
+        self.analysis_handle = Some(handle.abort_on_drop());
+        analysis
+    }
+
+    /// Hands the turn to the other side after a move, starting the AI search
+    /// in the background when it is O's turn
+    fn advance(&mut self) -> Task<Message> {
+        if self.status() != Status::Ongoing {
+            self.state = TicTacToe::Stopped;
+            return Task::none();
+        }
+        match self.state {
+            TicTacToe::Player1 => {
+                self.state = TicTacToe::Player2;
+                self.ai_move()
+            }
+            TicTacToe::Player2 => {
+                self.state = TicTacToe::Player1;
+                Task::none()
+            }
+            TicTacToe::Stopped | TicTacToe::Editing => Task::none(),
+        }
+    }
+
+    fn ai_move(&self) -> Task<Message> {
+        let position = Classic::new(self.grid.clone(), AI);
+        let game = self.game;
+        Task::perform(
+            async move { MiniMax::new(&position).best_move() },
+            move |placement| Message::AiMoved(game, placement),
+        )
+    }
+
+    fn status(&self) -> Status {
+        Classic::new(self.grid.clone(), HUMAN).status()
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let banner = match self.state {
+            TicTacToe::Player1 => "X to move".to_string(),
+            TicTacToe::Player2 => "O is thinking...".to_string(),
+            TicTacToe::Stopped => match self.status() {
+                Status::Won(FieldStates::Player1) => "X wins!".to_string(),
+                Status::Won(_) => "O wins!".to_string(),
+                _ => "Draw!".to_string(),
+            },
+            TicTacToe::Editing => "Set up a position".to_string(),
+        };
+        let scores = self
+            .analysis
+            .iter()
+            .flat_map(|analysis| {
+                analysis.scores.iter().map(|(placement, score)| {
+                    (
+                        placement.index,
+                        *score,
+                        format_score(*score, analysis.depth),
+                    )
+                })
+            })
+            .collect();
+        let input = match self.state {
+            TicTacToe::Player1 => grid::Input::Play,
+            TicTacToe::Editing => grid::Input::Edit,
+            _ => grid::Input::Disabled,
+        };
+        let board = canvas(grid::Grid::new(self.grid.clone(), input, scores))
+            .width(Fill)
+            .height(Fill);
+
+        let controls: Element<_> = match self.state {
+            TicTacToe::Editing => self.view_editor(),
+            _ => row![
+                button(text("New Game")).on_press(Message::NewGame),
+                button(text("Edit Position")).on_press(Message::Edit),
+            ]
+            .spacing(10)
+            .into(),
+        };
+
+        let game = column![text(banner).size(32), board, controls]
+            .spacing(20)
+            .align_x(Center)
+            .width(Fill);
+
+        let content = row![game, self.view_analysis()].spacing(20).padding(20);
 
         center(content).into()
     }
+
+    fn view_editor(&self) -> Element<'_, Message> {
+        let side = |label, side| {
+            button(text(label))
+                .on_press_maybe((self.to_move != side).then_some(Message::SideToMove(side)))
+        };
+        let valid = self.setup_error.is_none();
+
+        column![
+            row![
+                side("X to move", FieldStates::Player1),
+                side("O to move", FieldStates::Player2),
+            ]
+            .spacing(10),
+            text_editor(&self.position_text)
+                .on_action(Message::PositionTextEdited)
+                .height(100),
+            row![
+                button(text("Import")).on_press(Message::Import),
+                button(text("Export")).on_press(Message::Export),
+                button(text("Start Game")).on_press_maybe(valid.then_some(Message::StartGame)),
+                button(text("New Game")).on_press(Message::NewGame),
+            ]
+            .spacing(10),
+            text(self.setup_error.clone().unwrap_or_default()),
+        ]
+        .spacing(10)
+        .align_x(Center)
+        .into()
+    }
+
+    fn view_analysis(&self) -> Element<'_, Message> {
+        let details: Element<_> = match &self.analysis {
+            Some(analysis) => {
+                let (_, cols) = self.grid.size();
+                let line = analysis
+                    .principal_variation
+                    .iter()
+                    .map(|placement| {
+                        let symbol = match placement.value {
+                            FieldStates::Player1 => "X",
+                            _ => "O",
+                        };
+                        format!("{}{}", symbol, field_name(placement.index, cols))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                column![
+                    text(format!("Depth: {}", analysis.depth)),
+                    text(format!("Nodes: {}", analysis.nodes)),
+                    text("Best line:"),
+                    text(line),
+                ]
+                .spacing(10)
+                .into()
+            }
+            None if self.status() == Status::Ongoing => text("Thinking...").into(),
+            None => text("Game over").into(),
+        };
+
+        column![text("Analysis").size(24), details]
+            .spacing(20)
+            .width(220)
+            .into()
+    }
+}
+
+/// Analyses of `root`, each one search depth deeper, up to `max_depth`. Stops
+/// once `cancelled` is set, or once the search tree stops growing: every line
+/// was then searched to its end and deeper searches would only repeat it.
+fn deepen(
+    root: Classic,
+    max_depth: i8,
+    cancelled: Arc<AtomicBool>,
+) -> impl Iterator<Item = Analysis<Placement>> {
+    let mut nodes = 0;
+    (1..=max_depth)
+        .take_while(move |_| !cancelled.load(Ordering::Relaxed))
+        .map(move |depth| MiniMax::new(&root).with_depth(depth).analyse())
+        .take_while(move |analysis| {
+            let grown = analysis.nodes != nodes;
+            nodes = analysis.nodes;
+            grown
+        })
+}
+
+/// Score of a move as shown to the players: wins and losses as "W" or "L" with
+/// the number of moves until the game ends, anything else as the plain score
+fn format_score(score: i32, depth: i8) -> String {
+    if score >= 1000 {
+        format!("W{}", depth as i32 - (score - 1000))
+    } else if score <= -1000 {
+        format!("L{}", depth as i32 - (-score - 1000))
+    } else {
+        score.to_string()
+    }
 }
+
+/// Chess-like name of a field, columns as letters and rows as numbers from
+/// the top, e.g. "b2" for the centre
+fn field_name(index: usize, cols: usize) -> String {
+    let col = (b'a' + (index % cols) as u8) as char;
+    format!("{}{}", col, index / cols + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> TicTacToeApp {
+        TicTacToeApp::new().0
+    }
+
+    #[test]
+    fn moves_out_of_turn_or_on_taken_fields_are_ignored() {
+        let mut app = app();
+        let _ = app.update(Message::PlayerMoved((1, 1, HUMAN)));
+        assert_eq!(app.grid.get(1, 1), Some(&HUMAN));
+        assert!(matches!(app.state, TicTacToe::Player2));
+
+        // The AI is still thinking
+        let _ = app.update(Message::PlayerMoved((0, 0, HUMAN)));
+        assert_eq!(app.grid.get(0, 0), Some(&FieldStates::Empty));
+
+        let reply = Placement {
+            index: 0,
+            value: AI,
+        };
+        let _ = app.update(Message::AiMoved(app.game, Some(reply)));
+        assert!(matches!(app.state, TicTacToe::Player1));
+        // Taken by the AI, or not the human's symbol
+        let _ = app.update(Message::PlayerMoved((0, 0, HUMAN)));
+        let _ = app.update(Message::PlayerMoved((2, 2, AI)));
+        assert_eq!(app.grid.get(0, 0), Some(&AI));
+        assert_eq!(app.grid.get(2, 2), Some(&FieldStates::Empty));
+        assert!(matches!(app.state, TicTacToe::Player1));
+    }
+
+    #[test]
+    fn answers_from_an_earlier_game_are_dropped() {
+        let mut app = app();
+        let _ = app.update(Message::PlayerMoved((1, 1, HUMAN)));
+        let stale = app.game;
+        let _ = app.update(Message::NewGame);
+        let _ = app.update(Message::PlayerMoved((0, 0, HUMAN)));
+
+        let reply = Placement {
+            index: 4,
+            value: AI,
+        };
+        let _ = app.update(Message::AiMoved(stale, Some(reply)));
+        assert_eq!(app.grid.get(1, 1), Some(&FieldStates::Empty));
+        assert!(matches!(app.state, TicTacToe::Player2));
+    }
+
+    #[test]
+    fn new_positions_cancel_the_running_analysis() {
+        let mut app = app();
+        let cancelled = app.analysis_cancelled.clone();
+        let _ = app.update(Message::PlayerMoved((1, 1, HUMAN)));
+        assert!(cancelled.load(Ordering::Relaxed));
+        assert!(!app.analysis_cancelled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn deepening_stops_once_every_line_is_searched() {
+        // Two moves left, deeper than that there is nothing more to find
+        let grid = Grid::from_str("1 2 1\n1 2 2\n2 0 0").unwrap();
+        let root = Classic::new(grid, FieldStates::Player1);
+        let depths: Vec<i8> = deepen(root.clone(), 9, Arc::new(AtomicBool::new(false)))
+            .map(|analysis| analysis.depth)
+            .collect();
+        assert_eq!(depths, vec![1, 2]);
+
+        let cancelled = Arc::new(AtomicBool::new(true));
+        assert_eq!(deepen(root, 9, cancelled).count(), 0);
+    }
+
+    #[test]
+    fn x_is_expected_to_move_unless_ahead() {
+        let mut app = app();
+        assert_eq!(app.expected_to_move(), FieldStates::Player1);
+        app.grid = Grid::from_str("1 0 0\n0 0 0\n0 0 0").unwrap();
+        assert_eq!(app.expected_to_move(), FieldStates::Player2);
+        app.grid = Grid::from_str("1 2 0\n0 0 0\n0 0 0").unwrap();
+        assert_eq!(app.expected_to_move(), FieldStates::Player1);
+    }
+
+    #[test]
+    fn either_side_may_move_first_in_set_up_positions() {
+        let mut app = app();
+        let _ = app.update(Message::Edit);
+        let _ = app.update(Message::CellEdited((1, 1)));
+        let _ = app.update(Message::CellEdited((0, 0)));
+        let _ = app.update(Message::CellEdited((0, 0)));
+        assert_eq!(app.to_move, FieldStates::Player1);
+
+        let _ = app.update(Message::SideToMove(FieldStates::Player2));
+        assert_eq!(app.setup_error, None);
+        let _ = app.update(Message::StartGame);
+        assert!(matches!(app.state, TicTacToe::Player2));
+    }
+
+    #[test]
+    fn scores_count_the_moves_until_the_game_ends() {
+        assert_eq!(format_score(1008, 9), "W1");
+        assert_eq!(format_score(-1007, 9), "L2");
+        assert_eq!(format_score(1002, 3), "W1");
+        assert_eq!(format_score(-1000, 3), "L3");
+        assert_eq!(format_score(4, 3), "4");
+        assert_eq!(format_score(-999, 3), "-999");
+
+        // X wins on the spot in c1, draws by blocking in c2 and lets O win
+        // next with b3
+        let grid = Grid::from_str("1 1 0\n2 2 0\n1 0 2").unwrap();
+        let analysis = MiniMax::new(&Classic::new(grid, FieldStates::Player1)).analyse();
+        for (placement, score) in &analysis.scores {
+            let expected = match placement.index {
+                2 => "W1",
+                5 => "0",
+                _ => "L2",
+            };
+            assert_eq!(format_score(*score, analysis.depth), expected);
+        }
+    }
+
+    #[test]
+    fn fields_are_named_by_column_and_row() {
+        assert_eq!(field_name(0, 3), "a1");
+        assert_eq!(field_name(4, 3), "b2");
+        assert_eq!(field_name(8, 3), "c3");
+        assert_eq!(field_name(11, 4), "d3");
+    }
+}
+
 mod grid {
-    use iced::{mouse::Interaction, widget::canvas};
+    use iced::alignment;
+    use iced::mouse::{self, Interaction};
+    use iced::widget::canvas::{self, event, Event, Frame, Geometry, Path, Stroke, Text};
+    use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
     use tictactoe_logic::grid::{self, FieldStates};
+
+    use crate::{Message, HUMAN};
+
+    /// What clicks on the board do
+    pub enum Input {
+        Disabled,
+        /// Places the human's symbol through [`Message::PlayerMoved`]
+        Play,
+        /// Cycles the field through empty, X and O in the board editor
+        Edit,
+    }
+
+    /// Canvas program drawing the board and turning clicks on it into
+    /// messages
     pub struct Grid {
         grid: grid::Grid,
+        input: Input,
+        /// Engine scores of the empty fields with their labels, drawn as a
+        /// heat map below the symbols
+        scores: Vec<(usize, i32, String)>,
+    }
+
+    impl Grid {
+        pub fn new(grid: grid::Grid, input: Input, scores: Vec<(usize, i32, String)>) -> Self {
+            Self {
+                grid,
+                input,
+                scores,
+            }
+        }
+
+        /// The largest square that fits into `bounds`, centred
+        fn board(&self, bounds: Size) -> Rectangle {
+            let side = bounds.width.min(bounds.height);
+            Rectangle {
+                x: (bounds.width - side) / 2.0,
+                y: (bounds.height - side) / 2.0,
+                width: side,
+                height: side,
+            }
+        }
+
+        /// Row and column of the field under `position`, relative to the canvas
+        fn field_at(&self, bounds: Size, position: Point) -> Option<(usize, usize)> {
+            let board = self.board(bounds);
+            if !board.contains(position) {
+                return None;
+            }
+            let (rows, cols) = self.grid.size();
+            let row = ((position.y - board.y) / board.height * rows as f32) as usize;
+            let col = ((position.x - board.x) / board.width * cols as f32) as usize;
+            Some((row.min(rows - 1), col.min(cols - 1)))
+        }
+    }
+
+    impl canvas::Program<Message> for Grid {
+        type State = ();
+
+        fn update(
+            &self,
+            _state: &mut (),
+            event: Event,
+            bounds: Rectangle,
+            cursor: mouse::Cursor,
+        ) -> (event::Status, Option<Message>) {
+            match event {
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                    let field = cursor
+                        .position_in(bounds)
+                        .and_then(|position| self.field_at(bounds.size(), position));
+                    let message = match (&self.input, field) {
+                        (Input::Play, Some((row, col))) => {
+                            Message::PlayerMoved((row as i64, col as i64, HUMAN))
+                        }
+                        (Input::Edit, Some((row, col))) => {
+                            Message::CellEdited((row as i64, col as i64))
+                        }
+                        _ => return (event::Status::Ignored, None),
+                    };
+                    (event::Status::Captured, Some(message))
+                }
+                _ => (event::Status::Ignored, None),
+            }
+        }
+
+        fn draw(
+            &self,
+            _state: &(),
+            renderer: &Renderer,
+            theme: &Theme,
+            bounds: Rectangle,
+            _cursor: mouse::Cursor,
+        ) -> Vec<Geometry> {
+            let mut frame = Frame::new(renderer, bounds.size());
+            let board = self.board(bounds.size());
+            let (rows, cols) = self.grid.size();
+            let cell = Size::new(board.width / cols as f32, board.height / rows as f32);
+            let palette = theme.extended_palette();
+
+            for (index, score, label) in &self.scores {
+                let (row, col) = (index / cols, index % cols);
+                let top_left = Point::new(
+                    board.x + col as f32 * cell.width,
+                    board.y + row as f32 * cell.height,
+                );
+                frame.fill_rectangle(top_left, cell, heat(*score));
+                frame.fill_text(Text {
+                    content: label.clone(),
+                    position: Point::new(
+                        top_left.x + cell.width / 2.0,
+                        top_left.y + cell.height / 2.0,
+                    ),
+                    color: palette.background.base.text,
+                    size: (cell.height / 4.0).into(),
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                    ..Text::default()
+                });
+            }
+
+            let line = Stroke::default()
+                .with_color(palette.background.strong.color)
+                .with_width(4.0);
+
+            for col in 1..cols {
+                let x = board.x + col as f32 * cell.width;
+                frame.stroke(
+                    &Path::line(
+                        Point::new(x, board.y),
+                        Point::new(x, board.y + board.height),
+                    ),
+                    line,
+                );
+            }
+            for row in 1..rows {
+                let y = board.y + row as f32 * cell.height;
+                frame.stroke(
+                    &Path::line(Point::new(board.x, y), Point::new(board.x + board.width, y)),
+                    line,
+                );
+            }
+
+            let inset = cell.width.min(cell.height) * 0.2;
+            for (index, field) in self.grid.clone().into_iter().enumerate() {
+                let (row, col) = (index / cols, index % cols);
+                let center = Point::new(
+                    board.x + (col as f32 + 0.5) * cell.width,
+                    board.y + (row as f32 + 0.5) * cell.height,
+                );
+                let half = cell.width.min(cell.height) / 2.0 - inset;
+                match field {
+                    FieldStates::Player1 => {
+                        let stroke = Stroke::default()
+                            .with_color(Color::from_rgb8(0xE0, 0x4A, 0x4A))
+                            .with_width(8.0);
+                        frame.stroke(
+                            &Path::line(
+                                Point::new(center.x - half, center.y - half),
+                                Point::new(center.x + half, center.y + half),
+                            ),
+                            stroke,
+                        );
+                        frame.stroke(
+                            &Path::line(
+                                Point::new(center.x + half, center.y - half),
+                                Point::new(center.x - half, center.y + half),
+                            ),
+                            stroke,
+                        );
+                    }
+                    FieldStates::Player2 => {
+                        frame.stroke(
+                            &Path::circle(center, half),
+                            Stroke::default()
+                                .with_color(Color::from_rgb8(0x4A, 0x7A, 0xE0))
+                                .with_width(8.0),
+                        );
+                    }
+                    FieldStates::Empty => {}
+                }
+            }
+
+            vec![frame.into_geometry()]
+        }
+
+        fn mouse_interaction(
+            &self,
+            _state: &(),
+            bounds: Rectangle,
+            cursor: mouse::Cursor,
+        ) -> Interaction {
+            let field = cursor
+                .position_in(bounds)
+                .and_then(|position| self.field_at(bounds.size(), position))
+                .and_then(|(row, col)| self.grid.get(row, col).copied());
+            let clickable = match self.input {
+                Input::Disabled => false,
+                Input::Play => field == Some(FieldStates::Empty),
+                Input::Edit => field.is_some(),
+            };
+            if clickable {
+                Interaction::Pointer
+            } else {
+                Interaction::default()
+            }
+        }
+    }
+
+    /// Green for moves that win, red for moves that lose, grey in between
+    fn heat(score: i32) -> Color {
+        let strength = (score.abs().min(1000) as f32 / 1000.0).max(0.15) * 0.6;
+        match score.signum() {
+            1 => Color::from_rgba8(0x3C, 0xB0, 0x4A, strength),
+            -1 => Color::from_rgba8(0xE0, 0x4A, 0x4A, strength),
+            _ => Color::from_rgba8(0x90, 0x90, 0x90, strength),
+        }
     }
 }
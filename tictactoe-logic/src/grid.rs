@@ -1,6 +1,6 @@
 use std::{fmt::Display, str::FromStr};
 
-use crate::patterns::patterns;
+use crate::patterns::lines;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FieldStates {
@@ -32,63 +32,61 @@ impl FromStr for FieldStates {
     }
 }
 
+/// Content of a single field. Variants that don't place X and O, like
+/// numerical tic-tac-toe, bring their own cell type.
+pub trait Cell: Copy + PartialEq {
+    /// State of a field nobody has played on yet
+    const EMPTY: Self;
+
+    fn is_empty(&self) -> bool {
+        *self == Self::EMPTY
+    }
+}
+
+impl Cell for FieldStates {
+    const EMPTY: Self = FieldStates::Empty;
+}
+
 #[derive(Clone, Debug)]
-pub struct Grid {
+pub struct Grid<T = FieldStates> {
     rows: usize,
     cols: usize,
-    fields: Vec<FieldStates>,
+    /// Number of symbols in a row needed to win, defaults to the shorter side
+    win_length: usize,
+    fields: Vec<T>,
 }
-impl Grid {
-    pub fn new(rows: usize, cols: usize) -> Grid {
+impl<T: Cell> Grid<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
         Self {
             rows,
             cols,
+            win_length: rows.min(cols),
             fields: Vec::new(),
         }
         .populate()
     }
-    pub fn populate(mut self) -> Self {
-        self.fields.clear();
-        self.fields
-            .resize(self.rows * self.cols, FieldStates::Empty);
+    pub fn with_win_length(mut self, win_length: usize) -> Self {
+        self.win_length = win_length;
         self
     }
-    #[cfg(feature = "random")]
-    pub fn random<R: rand::Rng + ?Sized>(mut self, rng: &mut R) -> Self {
-        use rand::seq::*;
-        self = self.populate();
-        self.fields.iter_mut().for_each(move |value| {
-            *value = [
-                FieldStates::Empty,
-                FieldStates::Player1,
-                FieldStates::Player2,
-            ]
-            .choose(rng)
-            .unwrap()
-            .clone()
-        });
+    pub fn populate(mut self) -> Self {
+        self.fields.clear();
+        self.fields.resize(self.rows * self.cols, T::EMPTY);
         self
     }
 
-    pub fn get(&self, row: usize, col: usize) -> Option<&FieldStates> {
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
         self.fields.get(col + (self.cols * row))
     }
 
-    pub fn set(&mut self, row: usize, col: usize, new_state: FieldStates) -> Option<FieldStates> {
-        let field = self.fields.get_mut(col + (self.cols * row))?;
-        if matches!(field, FieldStates::Empty) {
-            let old = field.clone();
-            *field = new_state;
-            Some(old)
-        } else {
-            None
-        }
+    pub fn set(&mut self, row: usize, col: usize, new_state: T) -> Option<T> {
+        self.set_elem(col + (self.cols * row), new_state)
     }
 
-    pub fn set_elem(&mut self, element: usize, new_state: FieldStates) -> Option<FieldStates> {
+    pub fn set_elem(&mut self, element: usize, new_state: T) -> Option<T> {
         let field = self.fields.get_mut(element)?;
-        if matches!(field, FieldStates::Empty) {
-            let old = field.clone();
+        if field.is_empty() {
+            let old = *field;
             *field = new_state;
             Some(old)
         } else {
@@ -96,40 +94,68 @@ impl Grid {
         }
     }
 
+    /// Empties a field again, returning what stood on it
+    pub fn clear_elem(&mut self, element: usize) -> Option<T> {
+        let field = self.fields.get_mut(element)?;
+        Some(std::mem::replace(field, T::EMPTY))
+    }
+
+    pub fn get_elem(&self, element: usize) -> Option<&T> {
+        self.fields.get(element)
+    }
+
     pub fn size(&self) -> (usize, usize) {
         (self.rows, self.cols)
     }
 
-    pub fn check_win(&self, player: FieldStates) -> bool {
-        let possible_wins = patterns(self, player);
-        for pattern in possible_wins {
-            if self.matches(&pattern, player) {
-                return true;
-            }
-        }
-        false
+    pub fn win_length(&self) -> usize {
+        self.win_length
     }
 
-    fn matches(&self, pattern: &Grid, player: FieldStates) -> bool {
-        for (index, &field) in pattern.fields.iter().enumerate() {
-            if field == player && self.fields[index] != player {
-                return false;
-            }
-        }
-        true
+    /// Field indices of every line that can win on this grid
+    pub fn lines(&self) -> Vec<Vec<usize>> {
+        lines(self.rows, self.cols, self.win_length)
     }
+
     pub fn is_full(&self) -> bool {
-        for field in self.clone().into_iter() {
-            if matches!(field, FieldStates::Empty) {
-                return false;
-            }
-        }
-        true
+        !self.fields.iter().any(Cell::is_empty)
+    }
+}
+
+impl Grid {
+    #[cfg(feature = "random")]
+    pub fn random<R: rand::Rng + ?Sized>(mut self, rng: &mut R) -> Self {
+        use rand::seq::*;
+        self = self.populate();
+        self.fields.iter_mut().for_each(move |value| {
+            *value = *[
+                FieldStates::Empty,
+                FieldStates::Player1,
+                FieldStates::Player2,
+            ]
+            .choose(rng)
+            .unwrap()
+        });
+        self
     }
+
+    pub fn check_win(&self, player: FieldStates) -> bool {
+        self.lines()
+            .iter()
+            .any(|line| line.iter().all(|&index| self.fields[index] == player))
+    }
+
+    /// Whether any line is complete, regardless of the symbol. Used by the wild
+    /// variant, where completing a line of either symbol wins.
+    pub fn has_line(&self) -> bool {
+        self.check_win(FieldStates::Player1) || self.check_win(FieldStates::Player2)
+    }
+
     pub fn from_vec(rows: usize, cols: usize, fields: Vec<&str>) -> Self {
         Self {
             rows,
             cols,
+            win_length: rows.min(cols),
             fields: fields
                 .iter()
                 .map(|str| FieldStates::from_str(str).unwrap())
@@ -138,7 +164,7 @@ impl Grid {
     }
 }
 
-impl FromStr for Grid {
+impl<T: Cell + FromStr> FromStr for Grid<T> {
     type Err = String; // Using String as the error type for simplicity
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -164,7 +190,7 @@ impl FromStr for Grid {
             }
 
             for (col_idx, field) in fields.iter().enumerate() {
-                let state = field.parse::<FieldStates>().map_err(move |_| {
+                let state = field.parse::<T>().map_err(move |_| {
                     format!(
                         "Invalid field value '{}' at row {}, column {}",
                         field,
@@ -180,8 +206,8 @@ impl FromStr for Grid {
     }
 }
 
-impl IntoIterator for Grid {
-    type Item = FieldStates;
+impl<T> IntoIterator for Grid<T> {
+    type Item = T;
 
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
@@ -190,7 +216,7 @@ impl IntoIterator for Grid {
     }
 }
 
-impl Display for Grid {
+impl<T: Cell + Display> Display for Grid<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for row in 0..self.rows {
             for col in 0..self.cols {
@@ -211,8 +237,7 @@ impl Display for Grid {
 mod test {
     use crate::grid::{FieldStates, Grid};
 
-    use super::patterns;
-    use std::{fmt::Display, str::FromStr};
+    use std::str::FromStr;
 
     #[test]
     fn test_grid_from_str() {
@@ -268,7 +293,7 @@ mod test {
 
         for invalid_input in invalid_cases {
             assert!(
-                Grid::from_str(invalid_input).is_err(),
+                Grid::<FieldStates>::from_str(invalid_input).is_err(),
                 "Expected error for invalid input: {}",
                 invalid_input
             );
@@ -297,4 +322,20 @@ mod test {
         assert!(!grid.check_win(FieldStates::Player1));
         assert!(!grid.check_win(FieldStates::Player2));
     }
+
+    #[test]
+    pub fn check_win_with_win_length() {
+        // Four in a row is enough on a 5x5 board when the win length is 4
+        let grid = Grid::from_str("0 0 0 0 0\n0 1 1 1 1\n0 0 0 0 0\n2 2 2 0 0\n0 0 0 0 0").unwrap();
+        assert!(!grid.check_win(FieldStates::Player1));
+        let grid = grid.with_win_length(4);
+        assert!(grid.check_win(FieldStates::Player1));
+        assert!(!grid.check_win(FieldStates::Player2));
+
+        // Off-centre diagonal
+        let grid = Grid::from_str("0 0 0 0 0\n2 0 0 0 0\n0 2 0 0 0\n0 0 2 0 0\n0 0 0 2 0")
+            .unwrap()
+            .with_win_length(4);
+        assert!(grid.check_win(FieldStates::Player2));
+    }
 }
@@ -1,3 +1,7 @@
 pub mod grid;
 pub mod minimax;
+pub mod numerical;
+pub mod order_chaos;
 pub mod patterns;
+pub mod quantum;
+pub mod rules;
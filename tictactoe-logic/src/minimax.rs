@@ -1,77 +1,156 @@
-use crate::grid::{FieldStates, Grid};
+use crate::{
+    grid::FieldStates,
+    rules::{Rules, Status},
+};
+
+/// Game tree search over any [`Rules`]. Wins score 1000 plus the depth left,
+/// so quicker wins and slower losses are preferred.
 #[derive(Clone)]
-pub struct MiniMax {
-    grid: Grid,
+pub struct MiniMax<R> {
+    position: R,
+    max_depth: Option<i8>,
+    nodes: u64,
 }
 
-impl MiniMax {
-    pub fn new(grid: &Grid) -> Self {
-        Self { grid: grid.clone() }
-    }
-    pub fn calculate(&mut self, player: FieldStates) -> Grid {
-        let size = self.grid.size();
-        let (best_move, score) = self.minimax(
-            (size.1 * size.0) as i8,
-            true,
-            std::i8::MIN,
-            std::i8::MAX,
-            player,
-        );
-        self.grid.set_elem(best_move as usize, player);
-        self.grid.clone()
+/// Everything [`MiniMax::analyse`] found out about a position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis<M> {
+    /// Every legal move with its score for the side to move
+    pub scores: Vec<(M, i32)>,
+    /// Best play for both sides, starting with the best move
+    pub principal_variation: Vec<M>,
+    pub depth: i8,
+    pub nodes: u64,
+}
+
+impl<R: Rules> MiniMax<R> {
+    pub fn new(position: &R) -> Self {
+        Self {
+            position: position.clone(),
+            max_depth: None,
+            nodes: 0,
+        }
+    }
+
+    /// Limits how many moves ahead the search looks, positions at the limit
+    /// are scored by [`Rules::heuristic`]
+    pub fn with_depth(mut self, max_depth: i8) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    fn depth(&self) -> i8 {
+        self.max_depth
+            .or(self.position.default_depth())
+            .unwrap_or(i8::MAX)
+    }
+
+    /// Positions visited by the searches run so far
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Scores every legal move instead of just finding the best one. Slower
+    /// than [`MiniMax::best_move`] as each move gets a search of its own.
+    pub fn analyse(&mut self) -> Analysis<R::Move> {
+        let depth = self.depth();
+        let player = self.position.side_to_move();
+        let mut scores = Vec::new();
+        for next_move in self.position.legal_moves() {
+            self.position.apply(next_move);
+            let (_, score) = self.minimax((depth - 1).max(0), i32::MIN, i32::MAX, player);
+            self.position.undo(next_move);
+            scores.push((next_move, score));
+        }
+
+        // The first of the best moves, like the search itself picks
+        let best = scores
+            .iter()
+            .fold(None::<&(R::Move, i32)>, |best, scored| match best {
+                Some(best) if best.1 >= scored.1 => Some(best),
+                _ => Some(scored),
+            })
+            .map(|&(best, _)| best);
+        let mut principal_variation = Vec::new();
+        if let Some(best) = best {
+            let mut line = self.position.clone();
+            line.apply(best);
+            principal_variation.push(best);
+            // Followed until the game is over, not just as deep as the search
+            loop {
+                let mut search = MiniMax::new(&line).with_depth(depth);
+                let Some(next_move) = search.best_move() else {
+                    break;
+                };
+                self.nodes += search.nodes;
+                line.apply(next_move);
+                principal_variation.push(next_move);
+            }
+        }
+
+        Analysis {
+            scores,
+            principal_variation,
+            depth,
+            nodes: self.nodes,
+        }
+    }
+
+    /// Best move for the side to move, `None` once the game is over
+    pub fn best_move(&mut self) -> Option<R::Move> {
+        let player = self.position.side_to_move();
+        let (best_move, _) = self.minimax(self.depth(), i32::MIN, i32::MAX, player);
+        best_move
+    }
+
+    /// Plays the best move for the side to move and returns the new position
+    pub fn calculate(&mut self) -> R {
+        if let Some(best_move) = self.best_move() {
+            self.position.apply(best_move);
+        }
+        self.position.clone()
     }
 
     fn minimax(
-        &self,
+        &mut self,
         depth: i8,
-        maximize_win: bool,
-        mut alpha: i8,
-        mut beta: i8,
+        mut alpha: i32,
+        mut beta: i32,
         player: FieldStates,
-    ) -> (i8, i8) {
-        let opponent = match player {
-            FieldStates::Player1 => FieldStates::Player2,
-            FieldStates::Player2 => FieldStates::Player1,
-            _ => panic!("Invalid player state"),
-        };
-        if self.grid.check_win(player) {
-            return (-1, 10 + depth); // AI wins
-        }
-        if self.grid.check_win(opponent) {
-            return (-1, -10 - depth); // Opponent wins
+    ) -> (Option<R::Move>, i32) {
+        self.nodes += 1;
+        match self.position.status() {
+            Status::Won(winner) if winner == player => return (None, 1000 + depth as i32), // AI wins
+            Status::Won(_) => return (None, -1000 - depth as i32), // Opponent wins
+            Status::Draw => return (None, 0),                      // Tie
+            Status::Ongoing => {}
         }
 
-        if depth == 0 || self.grid.is_full() {
-            return (-1, 0); // Tie or max depth reached
+        let moves = self.position.legal_moves();
+        if depth == 0 || moves.is_empty() {
+            return (None, self.position.heuristic(player)); // Max depth reached
         }
 
-        let mut best_move = -1;
-        let mut best_score = if maximize_win {
-            std::i8::MIN
-        } else {
-            std::i8::MAX
-        };
+        // Not simply alternating, some variants let a side move twice in a row
+        let maximize_win = self.position.side_to_move() == player;
+        let mut best_move = None;
+        let mut best_score = if maximize_win { i32::MIN } else { i32::MAX };
 
-        for (i, cell) in self.grid.clone().into_iter().enumerate() {
-            if cell != FieldStates::Empty {
-                continue;
-            }
-            let mut next_move = self.clone();
-            next_move
-                .grid
-                .set_elem(i, if maximize_win { player } else { opponent });
-            let (_, score) = next_move.minimax(depth - 1, !maximize_win, alpha, beta, player);
+        for next_move in moves {
+            self.position.apply(next_move);
+            let (_, score) = self.minimax(depth - 1, alpha, beta, player);
+            self.position.undo(next_move);
 
             if maximize_win {
                 if score > best_score {
                     best_score = score;
-                    best_move = i as i8;
+                    best_move = Some(next_move);
                 }
                 alpha = alpha.max(best_score);
             } else {
                 if score < best_score {
                     best_score = score;
-                    best_move = i as i8;
+                    best_move = Some(next_move);
                 }
                 beta = beta.min(best_score);
             }
@@ -83,56 +162,47 @@ impl MiniMax {
 
         (best_move, best_score)
     }
-    pub fn calculate_without_pruning(&mut self, player: FieldStates) -> Grid {
-        let size = self.grid.size();
-        let (best_move, score) = self.minimax_simple((size.1 * size.0) as i8, true, player);
-        self.grid.set_elem(best_move as usize, player);
-        self.grid.clone()
-    }
 
-    fn minimax_simple(&self, depth: i8, maximize_win: bool, player: FieldStates) -> (i8, i8) {
-        let opponent = match player {
-            FieldStates::Player1 => FieldStates::Player2,
-            FieldStates::Player2 => FieldStates::Player1,
-            _ => panic!("Invalid player state"),
-        };
-
-        if self.grid.check_win(player) {
-            return (-1, 10 + depth); // AI wins
+    pub fn calculate_without_pruning(&mut self) -> R {
+        let player = self.position.side_to_move();
+        if let (Some(best_move), _) = self.minimax_simple(self.depth(), player) {
+            self.position.apply(best_move);
         }
-        if self.grid.check_win(opponent) {
-            return (-1, -10 - depth); // Opponent wins
+        self.position.clone()
+    }
+
+    fn minimax_simple(&mut self, depth: i8, player: FieldStates) -> (Option<R::Move>, i32) {
+        self.nodes += 1;
+        match self.position.status() {
+            Status::Won(winner) if winner == player => return (None, 1000 + depth as i32), // AI wins
+            Status::Won(_) => return (None, -1000 - depth as i32), // Opponent wins
+            Status::Draw => return (None, 0),                      // Tie
+            Status::Ongoing => {}
         }
-        if depth == 0 || self.grid.is_full() {
-            return (-1, 0); // Tie or max depth reached
+
+        let moves = self.position.legal_moves();
+        if depth == 0 || moves.is_empty() {
+            return (None, self.position.heuristic(player)); // Max depth reached
         }
 
-        let mut best_move = -1;
-        let mut best_score = if maximize_win {
-            std::i8::MIN
-        } else {
-            std::i8::MAX
-        };
+        let maximize_win = self.position.side_to_move() == player;
+        let mut best_move = None;
+        let mut best_score = if maximize_win { i32::MIN } else { i32::MAX };
 
-        for (i, cell) in self.grid.clone().into_iter().enumerate() {
-            if cell != FieldStates::Empty {
-                continue;
-            }
-            let mut next_move = self.clone();
-            next_move
-                .grid
-                .set_elem(i, if maximize_win { player } else { opponent });
-            let (_, score) = next_move.minimax_simple(depth - 1, !maximize_win, player);
+        for next_move in moves {
+            self.position.apply(next_move);
+            let (_, score) = self.minimax_simple(depth - 1, player);
+            self.position.undo(next_move);
 
             if maximize_win {
                 if score > best_score {
                     best_score = score;
-                    best_move = i as i8;
+                    best_move = Some(next_move);
                 }
             } else {
                 if score < best_score {
                     best_score = score;
-                    best_move = i as i8;
+                    best_move = Some(next_move);
                 }
             }
         }
@@ -144,7 +214,13 @@ impl MiniMax {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::grid::{FieldStates, Grid};
+    use crate::{
+        grid::{Cell, FieldStates, Grid},
+        numerical::{self, Number},
+        order_chaos::{OrderAndChaos, Outcome, Role},
+        rules::{Classic, Wild},
+    };
+    use std::str::FromStr;
 
     #[test]
     fn test_immediate_win() {
@@ -154,8 +230,9 @@ mod tests {
         grid.set(0, 1, FieldStates::Player1);
         grid.set(0, 2, FieldStates::Player1);
 
-        let mut minimax = MiniMax::new(&grid);
-        let result = minimax.calculate(FieldStates::Player2);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
+        let result = minimax.calculate();
+        let result = result.grid();
         assert_eq!(result.get(2, 2), Some(&FieldStates::Player2));
     }
 
@@ -165,8 +242,9 @@ mod tests {
         grid.set(0, 0, FieldStates::Player1);
         grid.set(1, 1, FieldStates::Player1);
 
-        let mut minimax = MiniMax::new(&grid);
-        let result = minimax.calculate(FieldStates::Player2);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
+        let result = minimax.calculate();
+        let result = result.grid();
 
         assert_eq!(result.get(2, 2), Some(&FieldStates::Player2));
     }
@@ -174,8 +252,9 @@ mod tests {
     #[test]
     fn test_empty_board() {
         let grid = Grid::new(3, 3);
-        let mut minimax = MiniMax::new(&grid);
-        let result = minimax.calculate(FieldStates::Player2);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
+        let result = minimax.calculate();
+        let result = result.grid();
 
         // The first move should be in a corner or center for optimal play
         let corner_or_center = vec![(0, 0), (0, 2), (2, 0), (2, 2), (1, 1)];
@@ -194,8 +273,9 @@ mod tests {
         grid.set(0, 2, FieldStates::Player1);
         grid.set(1, 0, FieldStates::Player1);
 
-        let mut minimax = MiniMax::new(&grid);
-        let result = minimax.calculate(FieldStates::Player2);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
+        let result = minimax.calculate();
+        let result = result.grid();
 
         // Player2 should choose to win rather than block
         assert_eq!(result.get(2, 2), Some(&FieldStates::Player2));
@@ -212,8 +292,9 @@ mod tests {
         grid.set(2, 0, FieldStates::Player2);
         grid.set(2, 1, FieldStates::Player1);
 
-        let mut minimax = MiniMax::new(&grid);
-        let result = minimax.calculate(FieldStates::Player2);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
+        let result = minimax.calculate();
+        let result = result.grid();
 
         // The only move left should be (1, 2) or (2, 2)
         assert!(
@@ -222,14 +303,179 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wild_completes_line_with_either_symbol() {
+        // Two O's in the top row: the AI finishes it with an O even though
+        // the rest of the board only holds X's
+        let mut grid = Grid::new(3, 3);
+        grid.set(0, 0, FieldStates::Player2);
+        grid.set(0, 1, FieldStates::Player2);
+        grid.set(1, 0, FieldStates::Player1);
+        grid.set(2, 2, FieldStates::Player1);
+
+        let mut minimax = MiniMax::new(&Wild::new(grid, FieldStates::Player1));
+        let result = minimax.calculate();
+        let result = result.grid();
+        assert_eq!(result.get(0, 2), Some(&FieldStates::Player2));
+        assert!(result.has_line());
+    }
+
+    #[test]
+    fn test_wild_first_player_wins() {
+        // Wild tic-tac-toe is a first-player win with perfect play
+        let mut minimax = MiniMax::new(&Wild::new(Grid::new(3, 3), FieldStates::Player1));
+        let (best_move, score) = minimax.minimax(9, i32::MIN, i32::MAX, FieldStates::Player1);
+        assert!(best_move.is_some());
+        assert!(score > 0, "expected a forced win, got score {}", score);
+    }
+
+    #[test]
+    fn test_order_completes_five() {
+        let mut grid = Grid::new(6, 6).with_win_length(5);
+        for col in 0..4 {
+            grid.set(2, col, FieldStates::Player1);
+        }
+        grid.set(0, 0, FieldStates::Player2);
+        grid.set(5, 5, FieldStates::Player2);
+
+        let mut minimax = MiniMax::new(&OrderAndChaos::from_grid(grid, Role::Order));
+        let result = minimax.calculate();
+        assert_eq!(result.grid().get(2, 4), Some(&FieldStates::Player1));
+        assert_eq!(result.outcome(), Outcome::Won(Role::Order));
+    }
+
+    #[test]
+    fn test_chaos_spoils_four() {
+        // The only open end has to get the other symbol
+        let mut grid = Grid::new(6, 6).with_win_length(5);
+        grid.set(3, 0, FieldStates::Player2);
+        for col in 1..5 {
+            grid.set(3, col, FieldStates::Player1);
+        }
+
+        let mut minimax = MiniMax::new(&OrderAndChaos::from_grid(grid, Role::Chaos));
+        let result = minimax.calculate();
+        assert_eq!(result.grid().get(3, 5), Some(&FieldStates::Player2));
+        assert_eq!(result.outcome(), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn test_with_depth() {
+        // A depth of one still sees the immediate win
+        let mut grid = Grid::new(3, 3);
+        grid.set(0, 0, FieldStates::Player2);
+        grid.set(1, 1, FieldStates::Player2);
+        grid.set(0, 1, FieldStates::Player1);
+        grid.set(0, 2, FieldStates::Player1);
+
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2)).with_depth(1);
+        let result = minimax.calculate();
+        let result = result.grid();
+        assert_eq!(result.get(2, 2), Some(&FieldStates::Player2));
+    }
+
+    #[test]
+    fn test_large_board_is_depth_limited() {
+        // Four in a row on 5x5, the depth limit keeps this quick
+        let mut grid = Grid::new(5, 5).with_win_length(4);
+        for col in 0..3 {
+            grid.set(2, col, FieldStates::Player2);
+            grid.set(4, col, FieldStates::Player1);
+        }
+        grid.set(0, 4, FieldStates::Player1);
+
+        let position = Classic::new(grid, FieldStates::Player2);
+        assert_eq!(position.default_depth(), Some(3));
+        let result = MiniMax::new(&position).calculate();
+        assert_eq!(result.grid().get(2, 3), Some(&FieldStates::Player2));
+        assert_eq!(result.status(), Status::Won(FieldStates::Player2));
+    }
+
+    #[test]
+    fn test_numerical_completes_fifteen() {
+        // 1 + 5 + 9 down the left column
+        let grid = Grid::<Number>::from_str("1 8 0\n5 0 0\n0 2 0").unwrap();
+        let mut minimax = MiniMax::new(&grid);
+        let result = minimax.calculate();
+        assert_eq!(result.get(2, 0), Some(&Number(9)));
+        assert!(numerical::check_win(&result));
+    }
+
+    #[test]
+    fn test_numerical_blocks_fifteen() {
+        // Player1 threatens 9 + 5 + 1 on the top row, Player2 has to fill the
+        // last field with one of its even numbers
+        let grid = Grid::<Number>::from_str("9 5 0\n0 0 0\n0 0 8").unwrap();
+        let mut minimax = MiniMax::new(&grid);
+        let result = minimax.calculate();
+        assert!(!result.get(0, 2).unwrap().is_empty());
+        assert!(!numerical::check_win(&result));
+    }
+
+    #[test]
+    fn test_without_pruning_agrees() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(0, 0, FieldStates::Player1);
+        grid.set(1, 1, FieldStates::Player1);
+        grid.set(0, 2, FieldStates::Player2);
+
+        let position = Classic::new(grid, FieldStates::Player2);
+        let pruned = MiniMax::new(&position).calculate();
+        let simple = MiniMax::new(&position).calculate_without_pruning();
+        assert_eq!(pruned.grid().get(2, 2), Some(&FieldStates::Player2));
+        assert_eq!(simple.grid().get(2, 2), Some(&FieldStates::Player2));
+    }
+
+    #[test]
+    fn test_analyse_scores_every_move() {
+        // X threatens the diagonal, O has to block in the corner
+        let grid = Grid::from_str("1 0 2\n0 1 0\n0 0 0").unwrap();
+        let position = Classic::new(grid, FieldStates::Player2);
+        let analysis = MiniMax::new(&position).analyse();
+
+        assert_eq!(analysis.scores.len(), 6);
+        assert_eq!(analysis.depth, i8::MAX);
+        assert!(analysis.nodes > 6);
+        for (placement, score) in &analysis.scores {
+            if placement.index == 8 {
+                assert_eq!(*score, 0);
+            } else {
+                assert!(*score < -1000, "{:?} scored {}", placement, score);
+            }
+        }
+
+        // Best play from here on, starting with the block
+        let line = &analysis.principal_variation;
+        assert_eq!(line[0].index, 8);
+        assert_eq!(line[0].value, FieldStates::Player2);
+        assert_eq!(line[1].value, FieldStates::Player1);
+        let mut played = position.clone();
+        line.iter().for_each(|&next_move| played.apply(next_move));
+        assert_eq!(played.status(), Status::Draw);
+    }
+
+    #[test]
+    fn test_principal_variation_reaches_past_the_search_depth() {
+        let position = Classic::new(Grid::new(3, 3), FieldStates::Player1);
+        let analysis = MiniMax::new(&position).with_depth(2).analyse();
+
+        let mut played = position.clone();
+        analysis
+            .principal_variation
+            .iter()
+            .for_each(|&next_move| played.apply(next_move));
+        assert!(analysis.principal_variation.len() > 2);
+        assert_ne!(played.status(), Status::Ongoing);
+    }
+
     #[test]
     fn test_performance() {
         let grid = Grid::new(3, 3);
-        let mut minimax = MiniMax::new(&grid);
+        let mut minimax = MiniMax::new(&Classic::new(grid, FieldStates::Player2));
 
         use std::time::Instant;
         let start = Instant::now();
-        minimax.calculate(FieldStates::Player2);
+        minimax.calculate();
         let duration = start.elapsed();
         assert!(duration.as_secs() < 1, "Minimax took too long to calculate");
     }
@@ -0,0 +1,220 @@
+//! Numerical tic-tac-toe.
+//!
+//! Player1 places the odd numbers, Player2 the even ones, and every number can
+//! only be used once. Whoever completes a line adding up to the target, 15 on
+//! the classic 3x3 board, wins.
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    grid::{Cell, FieldStates, Grid},
+    rules::{empty_fields, opponent, Placement, Rules, Status},
+};
+
+/// A number placed on the board, `0` marks an empty field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Number(pub u8);
+
+impl Cell for Number {
+    const EMPTY: Self = Number(0);
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Number {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Number).map_err(|_| ())
+    }
+}
+
+/// Search depth for the engines while many fields are open, every field
+/// offers each of the side's unused numbers
+pub const DEPTH: i8 = 3;
+
+/// Open fields up to which the engines search to the end of the game
+const FULL_SEARCH_FIELDS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ongoing,
+    Won(FieldStates),
+    Tie,
+}
+
+/// Sum a full line has to reach. This is the magic constant for the numbers
+/// `1..=rows * cols`, so 15 on a 3x3 board.
+pub fn target(grid: &Grid<Number>) -> u32 {
+    let (rows, cols) = grid.size();
+    (grid.win_length() * (rows * cols + 1) / 2) as u32
+}
+
+/// Whether any filled line adds up to the target. Uses the same lines as
+/// [`crate::patterns::patterns`].
+pub fn check_win(grid: &Grid<Number>) -> bool {
+    let target = target(grid);
+    grid.lines().iter().any(|line| {
+        line.iter()
+            .map(|&index| {
+                grid.get_elem(index)
+                    .filter(|number| !number.is_empty())
+                    .map(|number| number.0 as u32)
+            })
+            .sum::<Option<u32>>()
+            == Some(target)
+    })
+}
+
+/// Player1 starts, after that the sides alternate
+pub fn side_to_move(grid: &Grid<Number>) -> FieldStates {
+    let placed = grid
+        .clone()
+        .into_iter()
+        .filter(|number| !number.is_empty())
+        .count();
+    if placed % 2 == 0 {
+        FieldStates::Player1
+    } else {
+        FieldStates::Player2
+    }
+}
+
+/// Numbers `player` has not placed yet: odd ones for Player1, even ones for
+/// Player2
+pub fn available(grid: &Grid<Number>, player: FieldStates) -> Vec<Number> {
+    let (rows, cols) = grid.size();
+    let first = match player {
+        FieldStates::Player1 => 1,
+        FieldStates::Player2 => 2,
+        FieldStates::Empty => return Vec::new(),
+    };
+    let used = grid.clone().into_iter().collect::<Vec<_>>();
+    (first..=(rows * cols) as u8)
+        .step_by(2)
+        .map(Number)
+        .filter(|number| !used.contains(number))
+        .collect()
+}
+
+pub fn outcome(grid: &Grid<Number>) -> Outcome {
+    let to_move = side_to_move(grid);
+    if check_win(grid) {
+        // The line was completed by whoever moved last
+        Outcome::Won(opponent(to_move))
+    } else if grid.is_full() || available(grid, to_move).is_empty() {
+        Outcome::Tie
+    } else {
+        Outcome::Ongoing
+    }
+}
+
+/// Places `number` for the side to move. Returns `None` if the game is over,
+/// the field is taken or the number isn't available to that side.
+pub fn play(grid: &mut Grid<Number>, element: usize, number: Number) -> Option<Outcome> {
+    if outcome(grid) != Outcome::Ongoing || !available(grid, side_to_move(grid)).contains(&number) {
+        return None;
+    }
+    grid.set_elem(element, number)?;
+    Some(outcome(grid))
+}
+
+impl Rules for Grid<Number> {
+    type Move = Placement<Number>;
+
+    fn legal_moves(&self) -> Vec<Placement<Number>> {
+        if outcome(self) != Outcome::Ongoing {
+            return Vec::new();
+        }
+        let numbers = available(self, side_to_move(self));
+        empty_fields(self)
+            .into_iter()
+            .flat_map(|index| numbers.iter().map(move |&value| Placement { index, value }))
+            .collect()
+    }
+
+    fn apply(&mut self, played: Placement<Number>) {
+        self.set_elem(played.index, played.value);
+    }
+
+    fn undo(&mut self, played: Placement<Number>) {
+        self.clear_elem(played.index);
+    }
+
+    fn status(&self) -> Status {
+        match outcome(self) {
+            Outcome::Ongoing => Status::Ongoing,
+            Outcome::Won(player) => Status::Won(player),
+            Outcome::Tie => Status::Draw,
+        }
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        side_to_move(self)
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        (empty_fields(self).len() > FULL_SEARCH_FIELDS).then_some(DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn lines_summing_to_fifteen_win() {
+        let grid = Grid::<Number>::from_str("8 1 6\n0 0 0\n0 0 0").unwrap();
+        assert_eq!(target(&grid), 15);
+        assert!(check_win(&grid));
+        assert_eq!(outcome(&grid), Outcome::Won(FieldStates::Player1));
+
+        // Diagonal
+        let grid = Grid::<Number>::from_str("2 0 0\n0 5 0\n0 0 8").unwrap();
+        assert!(check_win(&grid));
+
+        // Full line with the wrong sum, or a line that isn't full yet
+        let grid = Grid::<Number>::from_str("1 2 3\n0 0 0\n9 6 0").unwrap();
+        assert!(!check_win(&grid));
+    }
+
+    #[test]
+    fn players_use_their_own_numbers_once() {
+        let mut grid = Grid::<Number>::new(3, 3);
+        assert_eq!(available(&grid, FieldStates::Player1).len(), 5);
+        assert_eq!(available(&grid, FieldStates::Player2).len(), 4);
+
+        // Player1 can't place an even number
+        assert_eq!(play(&mut grid, 0, Number(2)), None);
+        assert_eq!(play(&mut grid, 0, Number(7)), Some(Outcome::Ongoing));
+        assert_eq!(side_to_move(&grid), FieldStates::Player2);
+        assert_eq!(play(&mut grid, 1, Number(2)), Some(Outcome::Ongoing));
+        // 7 is used up and the middle of the top row is taken
+        assert_eq!(play(&mut grid, 4, Number(7)), None);
+        assert_eq!(play(&mut grid, 1, Number(5)), None);
+        assert_eq!(play(&mut grid, 8, Number(1)), Some(Outcome::Ongoing));
+        // 7 + 2 + 6 completes the top row for Player2
+        assert_eq!(
+            play(&mut grid, 2, Number(6)),
+            Some(Outcome::Won(FieldStates::Player2))
+        );
+        assert_eq!(play(&mut grid, 4, Number(3)), None);
+    }
+
+    #[test]
+    fn engines_answer_on_an_empty_board() {
+        let grid = Grid::<Number>::new(3, 3);
+        assert_eq!(grid.default_depth(), Some(DEPTH));
+        let best = crate::minimax::MiniMax::new(&grid).best_move().unwrap();
+        assert!(grid.legal_moves().contains(&best));
+
+        // Close to the end the search runs to the last move again
+        let grid = Grid::<Number>::from_str("1 8 0\n5 0 0\n0 2 0").unwrap();
+        assert_eq!(grid.default_depth(), None);
+    }
+}
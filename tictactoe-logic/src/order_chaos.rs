@@ -0,0 +1,229 @@
+//! Order and Chaos, an asymmetric variant on a 6x6 board.
+//!
+//! Both sides may place either symbol. Order wins as soon as five equal
+//! symbols stand in a row, Chaos wins if the board fills up without that.
+use crate::{
+    grid::{FieldStates, Grid},
+    rules::{empty_fields, Placement, Rules, Status},
+};
+
+pub const SIZE: usize = 6;
+pub const WIN_LENGTH: usize = 5;
+/// Search depth for the engines, the board is far too large to search to the end
+pub const DEPTH: i8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Order,
+    Chaos,
+}
+
+impl Role {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Role::Order => Role::Chaos,
+            Role::Chaos => Role::Order,
+        }
+    }
+
+    /// Order opens the game, so it plays as `Player1`
+    pub fn to_field_states(&self) -> FieldStates {
+        match self {
+            Role::Order => FieldStates::Player1,
+            Role::Chaos => FieldStates::Player2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ongoing,
+    Won(Role),
+}
+
+#[derive(Clone, Debug)]
+pub struct OrderAndChaos {
+    grid: Grid,
+    to_move: Role,
+}
+
+impl Default for OrderAndChaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderAndChaos {
+    /// Empty board with Order to move
+    pub fn new() -> Self {
+        Self {
+            grid: Grid::new(SIZE, SIZE).with_win_length(WIN_LENGTH),
+            to_move: Role::Order,
+        }
+    }
+
+    /// Continues from an existing position, the grid's win length is replaced
+    /// by [`WIN_LENGTH`]
+    pub fn from_grid(grid: Grid, to_move: Role) -> Self {
+        Self {
+            grid: grid.with_win_length(WIN_LENGTH),
+            to_move,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn to_move(&self) -> Role {
+        self.to_move
+    }
+
+    /// Places `symbol` on the given field for the side to move. Returns `None`
+    /// if the game is already over or the field is taken.
+    pub fn play(&mut self, element: usize, symbol: FieldStates) -> Option<Outcome> {
+        if symbol == FieldStates::Empty || self.outcome() != Outcome::Ongoing {
+            return None;
+        }
+        self.grid.set_elem(element, symbol)?;
+        self.to_move = self.to_move.opposite();
+        Some(self.outcome())
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        outcome(&self.grid)
+    }
+}
+
+pub fn outcome(grid: &Grid) -> Outcome {
+    if grid.has_line() {
+        Outcome::Won(Role::Order)
+    } else if grid.is_full() {
+        Outcome::Won(Role::Chaos)
+    } else {
+        Outcome::Ongoing
+    }
+}
+
+/// How close Order is to a line: every window not yet spoiled by both symbols
+/// counts with the square of its filled fields
+fn order_pressure(grid: &Grid) -> i32 {
+    grid.lines()
+        .iter()
+        .map(|line| {
+            let count = |state: FieldStates| {
+                line.iter()
+                    .filter(|&&index| grid.get_elem(index) == Some(&state))
+                    .count() as i32
+            };
+            let (x, o) = (count(FieldStates::Player1), count(FieldStates::Player2));
+            if x > 0 && o > 0 {
+                0
+            } else {
+                (x + o).pow(2)
+            }
+        })
+        .sum()
+}
+
+impl Rules for OrderAndChaos {
+    type Move = Placement;
+
+    fn legal_moves(&self) -> Vec<Placement> {
+        if self.outcome() != Outcome::Ongoing {
+            return Vec::new();
+        }
+        empty_fields(&self.grid)
+            .into_iter()
+            .flat_map(|index| {
+                [FieldStates::Player1, FieldStates::Player2].map(|value| Placement { index, value })
+            })
+            .collect()
+    }
+
+    fn apply(&mut self, played: Placement) {
+        self.grid.set_elem(played.index, played.value);
+        self.to_move = self.to_move.opposite();
+    }
+
+    fn undo(&mut self, played: Placement) {
+        self.grid.clear_elem(played.index);
+        self.to_move = self.to_move.opposite();
+    }
+
+    fn status(&self) -> Status {
+        match self.outcome() {
+            Outcome::Ongoing => Status::Ongoing,
+            Outcome::Won(role) => Status::Won(role.to_field_states()),
+        }
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        self.to_move.to_field_states()
+    }
+
+    fn heuristic(&self, player: FieldStates) -> i32 {
+        // Scores are asymmetric: pressure is good for Order, bad for Chaos
+        if player == Role::Order.to_field_states() {
+            order_pressure(&self.grid)
+        } else {
+            -order_pressure(&self.grid)
+        }
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        Some(DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order_wins_with_five_of_either_symbol() {
+        let mut game = OrderAndChaos::new();
+        for col in 0..4 {
+            assert_eq!(game.play(col, FieldStates::Player2), Some(Outcome::Ongoing));
+            // Chaos answers far away
+            assert_eq!(
+                game.play(30 + col, FieldStates::Player1),
+                Some(Outcome::Ongoing)
+            );
+        }
+        assert_eq!(
+            game.play(4, FieldStates::Player2),
+            Some(Outcome::Won(Role::Order))
+        );
+        // No more moves after the game ended
+        assert_eq!(game.play(5, FieldStates::Player2), None);
+    }
+
+    #[test]
+    fn chaos_wins_on_full_board() {
+        // Pairs of equal symbols, shifted every row, never line up five
+        let mut game = OrderAndChaos::new();
+        let mut last = None;
+        for element in 0..SIZE * SIZE {
+            let (row, col) = (element / SIZE, element % SIZE);
+            let symbol = if (row + col / 2) % 2 == 0 {
+                FieldStates::Player1
+            } else {
+                FieldStates::Player2
+            };
+            last = game.play(element, symbol);
+            if element + 1 < SIZE * SIZE {
+                assert_eq!(last, Some(Outcome::Ongoing), "line formed at {}", element);
+            }
+        }
+        assert_eq!(last, Some(Outcome::Won(Role::Chaos)));
+    }
+
+    #[test]
+    fn rejects_taken_fields() {
+        let mut game = OrderAndChaos::new();
+        game.play(0, FieldStates::Player1);
+        assert_eq!(game.play(0, FieldStates::Player2), None);
+        assert_eq!(game.to_move(), Role::Chaos);
+    }
+}
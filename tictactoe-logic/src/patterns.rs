@@ -1,43 +1,63 @@
 use crate::grid::{FieldStates, Grid};
 
-pub fn patterns(grid: &Grid, state: FieldStates) -> Vec<Grid> {
-    let (rows, cols) = grid.size();
-    // tic tac toe patterns
-    // Diagonals
-    let mut patterns = Vec::new();
-
-    // Diagonal from top-left to bottom-right
-    let mut diagonal1 = Grid::new(rows, cols).populate();
-    for i in 0..std::cmp::min(rows, cols) {
-        diagonal1.set(i, i, state.clone());
+/// Every run of `win_length` cells that wins when filled by one player, given
+/// as field indices.
+pub fn lines(rows: usize, cols: usize, win_length: usize) -> Vec<Vec<usize>> {
+    let mut lines = Vec::new();
+    if win_length == 0 || win_length > rows.max(cols) {
+        return lines;
     }
-    patterns.push(diagonal1);
+    let index = |row: usize, col: usize| col + cols * row;
 
-    // Diagonal from top-right to bottom-left
-    let mut diagonal2 = Grid::new(rows, cols);
-    for i in 0..std::cmp::min(rows, cols) {
-        diagonal2.set(i, cols - 1 - i, state.clone());
+    // Diagonals
+    if win_length <= rows.min(cols) {
+        // Diagonals from top-left to bottom-right
+        for row in 0..=rows - win_length {
+            for col in 0..=cols - win_length {
+                lines.push((0..win_length).map(|i| index(row + i, col + i)).collect());
+            }
+        }
+
+        // Diagonals from top-right to bottom-left
+        for row in 0..=rows - win_length {
+            for col in (win_length - 1..cols).rev() {
+                lines.push((0..win_length).map(|i| index(row + i, col - i)).collect());
+            }
+        }
     }
-    patterns.push(diagonal2);
 
     // Rows
-    for i in 0..rows {
-        let mut row = Grid::new(rows, cols);
-        for j in 0..cols {
-            row.set(i, j, state.clone());
+    if win_length <= cols {
+        for row in 0..rows {
+            for col in 0..=cols - win_length {
+                lines.push((0..win_length).map(|i| index(row, col + i)).collect());
+            }
         }
-        patterns.push(row);
     }
 
     // Columns
-    for j in 0..cols {
-        let mut col = Grid::new(rows, cols);
-        for i in 0..rows {
-            col.set(i, j, state.clone());
+    if win_length <= rows {
+        for col in 0..cols {
+            for row in 0..=rows - win_length {
+                lines.push((0..win_length).map(|i| index(row + i, col)).collect());
+            }
         }
-        patterns.push(col);
     }
-    patterns
+    lines
+}
+
+pub fn patterns(grid: &Grid, state: FieldStates) -> Vec<Grid> {
+    let (rows, cols) = grid.size();
+    lines(rows, cols, grid.win_length())
+        .into_iter()
+        .map(|line| {
+            let mut pattern = Grid::new(rows, cols).with_win_length(grid.win_length());
+            for index in line {
+                pattern.set_elem(index, state);
+            }
+            pattern
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -88,4 +108,19 @@ mod test {
             );
         }
     }
+
+    #[test]
+    pub fn lines_with_win_length() {
+        // 6x6 with five in a row: two windows per row and column, and four
+        // per diagonal direction
+        let lines = super::lines(6, 6, 5);
+        assert_eq!(lines.len(), 6 * 2 * 2 + 4 * 2);
+        assert!(lines.contains(&vec![0, 7, 14, 21, 28]));
+        assert!(lines.contains(&vec![11, 16, 21, 26, 31]));
+        assert!(lines.contains(&vec![31, 32, 33, 34, 35]));
+        assert!(lines.iter().all(|line| line.len() == 5));
+
+        // Nothing fits when the win length exceeds the board
+        assert!(super::lines(3, 3, 4).is_empty());
+    }
 }
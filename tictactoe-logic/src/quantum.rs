@@ -0,0 +1,519 @@
+//! Quantum tic-tac-toe.
+//!
+//! Every move places a spooky mark in two cells. Once the marks form a cycle
+//! the player who did not close it picks which of its two cells the closing
+//! mark collapses into, turning every mark entangled with it classical. The
+//! classical cells live in a regular [`Grid`].
+use std::fmt::Display;
+
+use crate::{
+    grid::{FieldStates, Grid},
+    patterns::lines,
+    rules::{opponent, Rules, Status},
+};
+
+/// Search depth for the engines, every turn offers dozens of spooky moves
+pub const DEPTH: i8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub player: FieldStates,
+    /// Move number the mark was placed on, starting at 1
+    pub turn: u8,
+    pub cells: (usize, usize),
+}
+
+impl Mark {
+    /// The other cell this mark is entangled with
+    fn partner(&self, cell: usize) -> usize {
+        if self.cells.0 == cell {
+            self.cells.1
+        } else {
+            self.cells.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantumMove {
+    /// Place a spooky mark in two different cells
+    Spooky(usize, usize),
+    /// Resolve a cycle by putting the mark that closed it into this cell
+    Collapse(usize),
+    /// Fill the last remaining cell, only possible once every other cell is classical
+    Classical(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Ongoing,
+    /// Points per player. If both complete a line in the same collapse, the
+    /// one whose line has the lower highest move number gets a full point and
+    /// the other half a point.
+    Finished {
+        player1: f32,
+        player2: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct QuantumGrid {
+    classical: Grid,
+    /// Move number of the mark each classical cell collapsed to
+    subscripts: Vec<Option<u8>>,
+    marks: Vec<Vec<Mark>>,
+    /// Mark that closed a cycle and is waiting for the opponent to collapse it
+    pending: Option<Mark>,
+    turn: u8,
+    /// State before each played move, so moves can be taken back
+    history: Vec<Snapshot>,
+}
+
+/// Everything a move can change
+#[derive(Clone, Debug)]
+struct Snapshot {
+    classical: Grid,
+    subscripts: Vec<Option<u8>>,
+    marks: Vec<Vec<Mark>>,
+    pending: Option<Mark>,
+    turn: u8,
+}
+
+impl QuantumGrid {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            classical: Grid::new(rows, cols),
+            subscripts: vec![None; rows * cols],
+            marks: vec![Vec::new(); rows * cols],
+            pending: None,
+            turn: 1,
+            history: Vec::new(),
+        }
+    }
+
+    /// Cells that have collapsed so far
+    pub fn classical(&self) -> &Grid {
+        &self.classical
+    }
+
+    /// Spooky marks currently in the given cell
+    pub fn marks(&self, element: usize) -> &[Mark] {
+        self.marks.get(element).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number the next placed mark gets
+    pub fn turn(&self) -> u8 {
+        self.turn
+    }
+
+    /// The two cells the cycle-closing mark may collapse into, if a collapse is due
+    pub fn pending_collapse(&self) -> Option<(usize, usize)> {
+        self.pending.map(|mark| mark.cells)
+    }
+
+    pub fn side_to_move(&self) -> FieldStates {
+        match self.pending {
+            // The player who didn't close the cycle decides how it collapses
+            Some(mark) => opponent(mark.player),
+            None if self.turn % 2 == 1 => FieldStates::Player1,
+            None => FieldStates::Player2,
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<QuantumMove> {
+        if self.outcome() != Outcome::Ongoing {
+            return Vec::new();
+        }
+        if let Some(mark) = self.pending {
+            return vec![
+                QuantumMove::Collapse(mark.cells.0),
+                QuantumMove::Collapse(mark.cells.1),
+            ];
+        }
+        let open = self.open_cells();
+        if let [last] = open.as_slice() {
+            return vec![QuantumMove::Classical(*last)];
+        }
+        let mut moves = Vec::new();
+        for (i, &a) in open.iter().enumerate() {
+            for &b in &open[i + 1..] {
+                moves.push(QuantumMove::Spooky(a, b));
+            }
+        }
+        moves
+    }
+
+    /// Plays a move for the side to move. Returns `None` if the move isn't legal.
+    pub fn play(&mut self, quantum_move: QuantumMove) -> Option<Outcome> {
+        if !self.legal_moves().contains(&quantum_move) {
+            return None;
+        }
+        let player = self.side_to_move();
+        self.history.push(Snapshot {
+            classical: self.classical.clone(),
+            subscripts: self.subscripts.clone(),
+            marks: self.marks.clone(),
+            pending: self.pending,
+            turn: self.turn,
+        });
+        match quantum_move {
+            QuantumMove::Spooky(a, b) => {
+                let mark = Mark {
+                    player,
+                    turn: self.turn,
+                    cells: (a, b),
+                };
+                let closes_cycle = self.entangled(a, b);
+                self.marks[a].push(mark);
+                self.marks[b].push(mark);
+                self.turn += 1;
+                if closes_cycle {
+                    self.pending = Some(mark);
+                }
+            }
+            QuantumMove::Collapse(cell) => {
+                let mark = self.pending.take()?;
+                self.collapse(mark, cell);
+            }
+            QuantumMove::Classical(cell) => {
+                self.classical.set_elem(cell, player)?;
+                self.subscripts[cell] = Some(self.turn);
+                self.turn += 1;
+            }
+        }
+        Some(self.outcome())
+    }
+
+    /// Takes back the last move. Returns `false` if no move was played yet.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.history.pop() else {
+            return false;
+        };
+        self.classical = snapshot.classical;
+        self.subscripts = snapshot.subscripts;
+        self.marks = snapshot.marks;
+        self.pending = snapshot.pending;
+        self.turn = snapshot.turn;
+        true
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        let (rows, cols) = self.classical.size();
+        // Highest move number of the earliest line per player
+        let mut player1 = None;
+        let mut player2 = None;
+        for line in lines(rows, cols, self.classical.win_length()) {
+            let Some(&owner) = self.classical.get_elem(line[0]) else {
+                continue;
+            };
+            if owner == FieldStates::Empty
+                || !line
+                    .iter()
+                    .all(|&index| self.classical.get_elem(index) == Some(&owner))
+            {
+                continue;
+            }
+            let completed = line
+                .iter()
+                .filter_map(|&index| self.subscripts[index])
+                .max()
+                .unwrap_or(0);
+            let best = if owner == FieldStates::Player1 {
+                &mut player1
+            } else {
+                &mut player2
+            };
+            *best = Some(best.map_or(completed, |best: u8| best.min(completed)));
+        }
+
+        match (player1, player2) {
+            (Some(x), Some(o)) if x < o => Outcome::Finished {
+                player1: 1.0,
+                player2: 0.5,
+            },
+            (Some(_), Some(_)) => Outcome::Finished {
+                player1: 0.5,
+                player2: 1.0,
+            },
+            (Some(_), None) => Outcome::Finished {
+                player1: 1.0,
+                player2: 0.0,
+            },
+            (None, Some(_)) => Outcome::Finished {
+                player1: 0.0,
+                player2: 1.0,
+            },
+            (None, None) if self.pending.is_none() && self.open_cells().is_empty() => {
+                Outcome::Finished {
+                    player1: 0.0,
+                    player2: 0.0,
+                }
+            }
+            (None, None) => Outcome::Ongoing,
+        }
+    }
+
+    fn open_cells(&self) -> Vec<usize> {
+        self.classical
+            .clone()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, field)| *field == FieldStates::Empty)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Whether two cells are already connected through spooky marks, in which
+    /// case a mark between them closes a cycle
+    fn entangled(&self, from: usize, to: usize) -> bool {
+        let mut visited = vec![false; self.marks.len()];
+        let mut queue = vec![from];
+        visited[from] = true;
+        while let Some(cell) = queue.pop() {
+            for mark in &self.marks[cell] {
+                let next = mark.partner(cell);
+                if next == to {
+                    return true;
+                }
+                if !visited[next] {
+                    visited[next] = true;
+                    queue.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Makes `mark` classical in `cell` and pushes every other mark in that
+    /// cell into its partner cell, which may collapse further cells
+    fn collapse(&mut self, mark: Mark, cell: usize) {
+        let displaced = std::mem::take(&mut self.marks[cell]);
+        let partner = mark.partner(cell);
+        self.marks[partner].retain(|other| *other != mark);
+        self.classical.set_elem(cell, mark.player);
+        self.subscripts[cell] = Some(mark.turn);
+
+        for other in displaced {
+            if other == mark || self.subscripts[other.partner(cell)].is_some() {
+                continue;
+            }
+            self.collapse(other, other.partner(cell));
+        }
+    }
+}
+
+impl Rules for QuantumGrid {
+    type Move = QuantumMove;
+
+    fn legal_moves(&self) -> Vec<QuantumMove> {
+        QuantumGrid::legal_moves(self)
+    }
+
+    fn apply(&mut self, played: QuantumMove) {
+        self.play(played);
+    }
+
+    /// Restores the snapshot of the last move, which `played` is by contract
+    fn undo(&mut self, _played: QuantumMove) {
+        QuantumGrid::undo(self);
+    }
+
+    /// A simultaneous win goes to whoever got the full point
+    fn status(&self) -> Status {
+        match self.outcome() {
+            Outcome::Ongoing => Status::Ongoing,
+            Outcome::Finished { player1, player2 } if player1 > player2 => {
+                Status::Won(FieldStates::Player1)
+            }
+            Outcome::Finished { player1, player2 } if player2 > player1 => {
+                Status::Won(FieldStates::Player2)
+            }
+            Outcome::Finished { .. } => Status::Draw,
+        }
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        QuantumGrid::side_to_move(self)
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        Some(DEPTH)
+    }
+}
+
+fn symbol(player: FieldStates, classical: bool) -> &'static str {
+    match (player, classical) {
+        (FieldStates::Player1, true) => "X",
+        (FieldStates::Player2, true) => "O",
+        (FieldStates::Player1, false) => "x",
+        (FieldStates::Player2, false) => "o",
+        (FieldStates::Empty, _) => ".",
+    }
+}
+
+/// Classical cells print as `X3`, spooky marks as lowercase `x1 o2`, empty
+/// cells as `.`
+impl Display for QuantumGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (rows, cols) = self.classical.size();
+        let cells = (0..rows * cols)
+            .map(
+                |index| match (self.classical.get_elem(index), self.subscripts[index]) {
+                    (Some(&player), Some(turn)) if player != FieldStates::Empty => {
+                        format!("{}{}", symbol(player, true), turn)
+                    }
+                    _ if self.marks[index].is_empty() => ".".to_string(),
+                    _ => self.marks[index]
+                        .iter()
+                        .map(|mark| format!("{}{}", symbol(mark.player, false), mark.turn))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                },
+            )
+            .collect::<Vec<_>>();
+        let width = cells.iter().map(String::len).max().unwrap_or(1);
+
+        for row in 0..rows {
+            if row > 0 {
+                writeln!(f, "{}", vec!["-".repeat(width + 2); cols].join("+"))?;
+            }
+            let line = (0..cols)
+                .map(|col| format!(" {:<width$} ", cells[col + cols * row]))
+                .collect::<Vec<_>>()
+                .join("|");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cycle_collapses_by_opponents_choice() {
+        let mut game = QuantumGrid::new(3, 3);
+        assert_eq!(game.legal_moves().len(), 36);
+
+        assert_eq!(game.play(QuantumMove::Spooky(0, 1)), Some(Outcome::Ongoing));
+        assert_eq!(game.play(QuantumMove::Spooky(1, 2)), Some(Outcome::Ongoing));
+        assert_eq!(game.pending_collapse(), None);
+        // x3 closes the cycle 0-1-2, so O decides where it lands
+        assert_eq!(game.play(QuantumMove::Spooky(0, 2)), Some(Outcome::Ongoing));
+        assert_eq!(game.pending_collapse(), Some((0, 2)));
+        assert_eq!(game.side_to_move(), FieldStates::Player2);
+        assert_eq!(
+            game.legal_moves(),
+            vec![QuantumMove::Collapse(0), QuantumMove::Collapse(2)]
+        );
+        assert_eq!(game.play(QuantumMove::Spooky(3, 4)), None);
+
+        game.play(QuantumMove::Collapse(0));
+        // x3 takes 0, pushing x1 to 1, which pushes o2 to 2
+        assert_eq!(game.classical().get_elem(0), Some(&FieldStates::Player1));
+        assert_eq!(game.classical().get_elem(1), Some(&FieldStates::Player1));
+        assert_eq!(game.classical().get_elem(2), Some(&FieldStates::Player2));
+        assert!((0..3).all(|cell| game.marks(cell).is_empty()));
+        // O, who resolved the cycle, now places mark 4
+        assert_eq!(game.side_to_move(), FieldStates::Player2);
+        assert_eq!(game.turn(), 4);
+        assert_eq!(game.legal_moves().len(), 15);
+    }
+
+    #[test]
+    fn simultaneous_wins_favour_the_earlier_line() {
+        let mut game = QuantumGrid::new(3, 3);
+        // X owns the top row with marks up to 5, O the bottom row up to 6
+        for (cell, player, turn) in [
+            (0, FieldStates::Player1, 1),
+            (1, FieldStates::Player1, 3),
+            (2, FieldStates::Player1, 5),
+            (6, FieldStates::Player2, 2),
+            (7, FieldStates::Player2, 4),
+            (8, FieldStates::Player2, 6),
+        ] {
+            game.classical.set_elem(cell, player);
+            game.subscripts[cell] = Some(turn);
+        }
+        assert_eq!(
+            game.outcome(),
+            Outcome::Finished {
+                player1: 1.0,
+                player2: 0.5
+            }
+        );
+        assert!(game.legal_moves().is_empty());
+
+        game.subscripts[2] = Some(7);
+        assert_eq!(
+            game.outcome(),
+            Outcome::Finished {
+                player1: 0.5,
+                player2: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn undo_restores_entanglement() {
+        let mut game = QuantumGrid::new(3, 3);
+        game.play(QuantumMove::Spooky(0, 1));
+        game.play(QuantumMove::Spooky(1, 2));
+        game.play(QuantumMove::Spooky(0, 2));
+        game.play(QuantumMove::Collapse(2));
+        assert_eq!(game.classical().get_elem(2), Some(&FieldStates::Player1));
+
+        assert!(game.undo());
+        assert_eq!(game.pending_collapse(), Some((0, 2)));
+        assert_eq!(game.classical().get_elem(2), Some(&FieldStates::Empty));
+        assert_eq!(game.marks(2).len(), 2);
+        assert!(game.undo());
+        assert_eq!(game.pending_collapse(), None);
+        assert_eq!(game.turn(), 3);
+    }
+
+    #[test]
+    fn engine_collapses_into_a_win() {
+        use crate::minimax::MiniMax;
+
+        // After the first collapse X owns 0 and 1 of the top row and O the
+        // centre. x5 then closes the cycle o4-x5 between 2 and 8
+        let mut game = QuantumGrid::new(3, 3);
+        for played in [
+            QuantumMove::Spooky(0, 1),
+            QuantumMove::Spooky(1, 4),
+            QuantumMove::Spooky(0, 4),
+            QuantumMove::Collapse(0),
+            QuantumMove::Spooky(2, 8),
+            QuantumMove::Spooky(2, 8),
+        ] {
+            assert_eq!(game.play(played), Some(Outcome::Ongoing), "{}", game);
+        }
+        assert_eq!(game.side_to_move(), FieldStates::Player2);
+
+        // x5 into 2 would finish X's top row, so O sends it to 8 instead
+        let result = MiniMax::new(&game).calculate();
+        assert_eq!(result.classical().get_elem(2), Some(&FieldStates::Player2));
+        assert_eq!(result.classical().get_elem(8), Some(&FieldStates::Player1));
+    }
+
+    #[test]
+    fn renders_marks() {
+        let mut game = QuantumGrid::new(3, 3);
+        game.play(QuantumMove::Spooky(0, 4));
+        game.play(QuantumMove::Spooky(0, 8));
+        game.play(QuantumMove::Spooky(4, 8));
+        // x3 lands in the centre, which sends x1 to 0 and o2 to 8
+        game.play(QuantumMove::Collapse(4));
+
+        assert_eq!(
+            game.to_string(),
+            " X1 | .  | .\n\
+             ----+----+----\n \
+             .  | X3 | .\n\
+             ----+----+----\n \
+             .  | .  | O2\n"
+        );
+    }
+}
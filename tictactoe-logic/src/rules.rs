@@ -0,0 +1,411 @@
+//! Rule sets the engines and frontends are generic over.
+//!
+//! A variant only has to describe its moves and when the game ends, search
+//! and the frontends drive it through [`Rules`].
+use crate::grid::{Cell, FieldStates, Grid};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ongoing,
+    Won(FieldStates),
+    Draw,
+}
+
+/// Puts `value` on the field with the given index. The move type of every
+/// variant that fills one field per turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placement<T = FieldStates> {
+    pub index: usize,
+    pub value: T,
+}
+
+pub trait Rules: Clone {
+    type Move: Copy + PartialEq + std::fmt::Debug;
+
+    /// Every move the side to move may play, empty once the game is over
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    fn apply(&mut self, played: Self::Move);
+
+    /// Takes back `played`, which has to be the last applied move. Calls
+    /// pair up with [`Rules::apply`] last in, first out, so implementations
+    /// may restore the position from before the last move and ignore
+    /// `played`.
+    fn undo(&mut self, played: Self::Move);
+
+    fn status(&self) -> Status;
+
+    /// `Player1` or `Player2`, whoever has to move next
+    fn side_to_move(&self) -> FieldStates;
+
+    /// Estimate for positions the search stops at before the game ends, from
+    /// `player`'s point of view. Must stay well below 1000 in either direction.
+    fn heuristic(&self, _player: FieldStates) -> i32 {
+        0
+    }
+
+    /// How many moves ahead the engines look unless told otherwise, `None`
+    /// searches until the game ends
+    fn default_depth(&self) -> Option<i8> {
+        None
+    }
+}
+
+/// Search depth for boards with more open fields than the classic 3x3 board,
+/// which can't be searched to the end in reasonable time
+pub const LARGE_BOARD_DEPTH: i8 = 3;
+
+fn large_board_depth(grid: &Grid) -> Option<i8> {
+    (empty_fields(grid).len() > 9).then_some(LARGE_BOARD_DEPTH)
+}
+
+/// Bound of [`line_pressure`]. Large boards hold enough windows to add up to
+/// nearly the score of a win, which the search must always prefer.
+const MAX_PRESSURE: i32 = 500;
+
+/// How close `player` is to a line: every window holding only their symbols
+/// counts with the square of its filled fields, the opponent's count against
+fn line_pressure(grid: &Grid, player: FieldStates) -> i32 {
+    grid.lines()
+        .iter()
+        .map(|line| {
+            let count = |state: FieldStates| {
+                line.iter()
+                    .filter(|&&index| grid.get_elem(index) == Some(&state))
+                    .count() as i32
+            };
+            let (own, theirs) = (count(player), count(opponent(player)));
+            match (own, theirs) {
+                (own, 0) => own.pow(2),
+                (0, theirs) => -theirs.pow(2),
+                _ => 0,
+            }
+        })
+        .sum::<i32>()
+        .clamp(-MAX_PRESSURE, MAX_PRESSURE)
+}
+
+pub fn opponent(player: FieldStates) -> FieldStates {
+    match player {
+        FieldStates::Player1 => FieldStates::Player2,
+        FieldStates::Player2 => FieldStates::Player1,
+        _ => panic!("Invalid player state"),
+    }
+}
+
+/// Any of the legal moves, for opponents that are meant to be beatable
+#[cfg(feature = "random")]
+pub fn random_move<R: Rules, G: rand::Rng + ?Sized>(position: &R, rng: &mut G) -> Option<R::Move> {
+    use rand::seq::SliceRandom;
+    position.legal_moves().choose(rng).copied()
+}
+
+/// Fields nobody has played on yet
+pub fn empty_fields<T: Cell>(grid: &Grid<T>) -> Vec<usize> {
+    grid.clone()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, field)| field.is_empty())
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Regular tic-tac-toe: the side to move places its own symbol, a line of
+/// your own symbol wins
+#[derive(Clone, Debug)]
+pub struct Classic {
+    grid: Grid,
+    to_move: FieldStates,
+}
+
+impl Classic {
+    pub fn new(grid: Grid, to_move: FieldStates) -> Self {
+        Self { grid, to_move }
+    }
+
+    /// Checks that the position can come up in a game either side opened:
+    /// the symbol counts differ by at most one, at most one side has a line,
+    /// and `to_move` neither moved last by the counts nor lost already. With
+    /// as many X as O either side may be to move.
+    pub fn setup(grid: Grid, to_move: FieldStates) -> Result<Self, String> {
+        let count = |state: FieldStates| {
+            grid.clone()
+                .into_iter()
+                .filter(|field| *field == state)
+                .count()
+        };
+        let (x, o) = (count(FieldStates::Player1), count(FieldStates::Player2));
+        if x.abs_diff(o) > 1 {
+            return Err(format!(
+                "X and O can't be more than one symbol apart, found {} X and {} O",
+                x, o
+            ));
+        }
+
+        let (x_won, o_won) = (
+            grid.check_win(FieldStates::Player1),
+            grid.check_win(FieldStates::Player2),
+        );
+        if x_won && o_won {
+            return Err("Both sides have a line".to_string());
+        }
+        // Whoever placed more symbols, or completed a line, moved last
+        let moved_last = if x > o || x_won {
+            Some(FieldStates::Player1)
+        } else if o > x || o_won {
+            Some(FieldStates::Player2)
+        } else {
+            None
+        };
+        if (x_won && o > x) || (o_won && x > o) {
+            return Err("The winner has to have made the last move".to_string());
+        }
+        if moved_last == Some(to_move) {
+            return Err(format!(
+                "{} has to move in this position",
+                if to_move == FieldStates::Player1 {
+                    "O"
+                } else {
+                    "X"
+                }
+            ));
+        }
+
+        Ok(Self::new(grid, to_move))
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl Rules for Classic {
+    type Move = Placement;
+
+    fn legal_moves(&self) -> Vec<Placement> {
+        if self.status() != Status::Ongoing {
+            return Vec::new();
+        }
+        empty_fields(&self.grid)
+            .into_iter()
+            .map(|index| Placement {
+                index,
+                value: self.to_move,
+            })
+            .collect()
+    }
+
+    fn apply(&mut self, played: Placement) {
+        self.grid.set_elem(played.index, played.value);
+        self.to_move = opponent(self.to_move);
+    }
+
+    fn undo(&mut self, played: Placement) {
+        self.grid.clear_elem(played.index);
+        self.to_move = opponent(self.to_move);
+    }
+
+    fn status(&self) -> Status {
+        if self.grid.check_win(FieldStates::Player1) {
+            Status::Won(FieldStates::Player1)
+        } else if self.grid.check_win(FieldStates::Player2) {
+            Status::Won(FieldStates::Player2)
+        } else if self.grid.is_full() {
+            Status::Draw
+        } else {
+            Status::Ongoing
+        }
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        self.to_move
+    }
+
+    fn heuristic(&self, player: FieldStates) -> i32 {
+        line_pressure(&self.grid, player)
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        large_board_depth(&self.grid)
+    }
+}
+
+/// Wild tic-tac-toe: either side may place either symbol, whoever completes a
+/// line wins
+#[derive(Clone, Debug)]
+pub struct Wild {
+    grid: Grid,
+    to_move: FieldStates,
+}
+
+impl Wild {
+    pub fn new(grid: Grid, to_move: FieldStates) -> Self {
+        Self { grid, to_move }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl Rules for Wild {
+    type Move = Placement;
+
+    fn legal_moves(&self) -> Vec<Placement> {
+        if self.status() != Status::Ongoing {
+            return Vec::new();
+        }
+        empty_fields(&self.grid)
+            .into_iter()
+            .flat_map(|index| {
+                [FieldStates::Player1, FieldStates::Player2].map(|value| Placement { index, value })
+            })
+            .collect()
+    }
+
+    fn apply(&mut self, played: Placement) {
+        self.grid.set_elem(played.index, played.value);
+        self.to_move = opponent(self.to_move);
+    }
+
+    fn undo(&mut self, played: Placement) {
+        self.grid.clear_elem(played.index);
+        self.to_move = opponent(self.to_move);
+    }
+
+    fn status(&self) -> Status {
+        if self.grid.has_line() {
+            // Whoever completed the line made the last move
+            Status::Won(opponent(self.to_move))
+        } else if self.grid.is_full() {
+            Status::Draw
+        } else {
+            Status::Ongoing
+        }
+    }
+
+    fn side_to_move(&self) -> FieldStates {
+        self.to_move
+    }
+
+    fn default_depth(&self) -> Option<i8> {
+        large_board_depth(&self.grid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn classic_apply_and_undo() {
+        let mut classic = Classic::new(Grid::new(3, 3), FieldStates::Player1);
+        assert_eq!(classic.legal_moves().len(), 9);
+
+        let played = Placement {
+            index: 4,
+            value: FieldStates::Player1,
+        };
+        classic.apply(played);
+        assert_eq!(classic.side_to_move(), FieldStates::Player2);
+        assert_eq!(classic.legal_moves().len(), 8);
+        assert!(classic
+            .legal_moves()
+            .iter()
+            .all(|placement| placement.value == FieldStates::Player2));
+
+        classic.undo(played);
+        assert_eq!(classic.side_to_move(), FieldStates::Player1);
+        assert_eq!(classic.grid().get_elem(4), Some(&FieldStates::Empty));
+    }
+
+    #[test]
+    fn classic_status() {
+        let grid = Grid::from_str("1 1 1\n2 2 0\n0 0 0").unwrap();
+        let classic = Classic::new(grid, FieldStates::Player2);
+        assert_eq!(classic.status(), Status::Won(FieldStates::Player1));
+        assert!(classic.legal_moves().is_empty());
+
+        let grid = Grid::from_str("1 2 1\n1 2 2\n2 1 1").unwrap();
+        assert_eq!(
+            Classic::new(grid, FieldStates::Player2).status(),
+            Status::Draw
+        );
+    }
+
+    #[test]
+    fn classic_setup_rejects_impossible_positions() {
+        let setup =
+            |position: &str, to_move| Classic::setup(Grid::from_str(position).unwrap(), to_move);
+
+        assert!(setup("1 0 0\n0 2 0\n0 0 0", FieldStates::Player1).is_ok());
+        assert!(setup("1 0 0\n0 2 0\n0 0 1", FieldStates::Player2).is_ok());
+        // With as many X as O either side may have opened
+        assert!(setup("1 0 0\n0 2 0\n0 0 0", FieldStates::Player2).is_ok());
+        assert!(setup("2 0 0\n0 0 0\n0 0 0", FieldStates::Player1).is_ok());
+        // Side to move placed more symbols
+        assert!(setup("1 0 0\n0 2 0\n0 0 1", FieldStates::Player1).is_err());
+        assert!(setup("2 0 0\n0 0 0\n0 0 0", FieldStates::Player2).is_err());
+        // Too many symbols for O, or for X
+        assert!(setup("2 0 0\n0 2 0\n0 0 0", FieldStates::Player1).is_err());
+        assert!(setup("1 1 0\n0 0 0\n0 0 0", FieldStates::Player2).is_err());
+        // Both sides have a line
+        assert!(setup("1 1 1\n2 2 2\n0 0 1", FieldStates::Player2).is_err());
+        // O can't have won while X moved last
+        assert!(setup("2 2 2\n1 1 0\n1 0 1", FieldStates::Player2).is_err());
+        assert!(setup("1 1 1\n2 2 0\n0 0 0", FieldStates::Player2).is_ok());
+        // X completed the line in a game O opened, so O is to move
+        assert!(setup("1 1 1\n2 2 0\n2 0 0", FieldStates::Player2).is_ok());
+        assert!(setup("1 1 1\n2 2 0\n2 0 0", FieldStates::Player1).is_err());
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn random_move_is_legal() {
+        let mut rng = rand::thread_rng();
+        let classic = Classic::new(
+            Grid::from_str("1 2 1\n2 1 0\n0 0 2").unwrap(),
+            FieldStates::Player1,
+        );
+        for _ in 0..20 {
+            let played = random_move(&classic, &mut rng).unwrap();
+            assert!(classic.legal_moves().contains(&played));
+        }
+
+        let finished = Classic::new(
+            Grid::from_str("1 1 1\n2 2 0\n0 0 0").unwrap(),
+            FieldStates::Player2,
+        );
+        assert_eq!(random_move(&finished, &mut rng), None);
+    }
+
+    #[test]
+    fn line_pressure_stays_clear_of_win_scores() {
+        // Every line of the 7x7 board is one field short of complete
+        let grid = Grid::from_str(
+            "1 1 1 1 0 1 1\n1 1 1 1 1 1 0\n1 0 1 1 1 1 1\n1 1 1 0 1 1 1\n\
+             1 1 1 1 1 0 1\n0 1 1 1 1 1 1\n1 1 0 1 1 1 1",
+        )
+        .unwrap()
+        .with_win_length(7);
+        let classic = Classic::new(grid, FieldStates::Player2);
+        assert_eq!(classic.status(), Status::Ongoing);
+        assert_eq!(classic.heuristic(FieldStates::Player1), MAX_PRESSURE);
+        assert_eq!(classic.heuristic(FieldStates::Player2), -MAX_PRESSURE);
+    }
+
+    #[test]
+    fn wild_line_belongs_to_last_mover() {
+        let grid = Grid::from_str("2 2 0\n1 0 0\n0 0 1").unwrap();
+        let mut wild = Wild::new(grid, FieldStates::Player2);
+        assert_eq!(wild.legal_moves().len(), 10);
+
+        wild.apply(Placement {
+            index: 2,
+            value: FieldStates::Player2,
+        });
+        assert_eq!(wild.status(), Status::Won(FieldStates::Player2));
+    }
+}